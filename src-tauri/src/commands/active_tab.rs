@@ -0,0 +1,289 @@
+use chrono::{Duration, Local, Utc};
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+use crate::utils::db::connection;
+use crate::utils::time::format_duration;
+use super::afk_tracker::is_afk;
+use super::browser::collect_history;
+use super::browser_categories::{categorize_url, split_url};
+use super::browser_redaction::redact_title;
+use super::private_browsing::{detect_private_window, track_private_foreground};
+use super::screenshot_triggers::on_site_visit;
+use super::visible_apps::get_foreground_window;
+
+/// How far back history is searched for a visit matching the window title.
+const RECENT_VISIT_HOURS: i64 = 24;
+
+/// A browser's history is re-read at most this often while it is in the foreground.
+const HISTORY_REFRESH_SECS: u64 = 60;
+
+/// Foreground time is written to `ems_data.db` this often; at most this much is lost on exit.
+const FLUSH_SECS: u64 = 30;
+
+/// Longer gaps between two samples (e.g. the machine was asleep) are credited as this much.
+const MAX_SAMPLE_GAP_MS: i64 = 5_000;
+
+/// Browser executables and the suffixes they append to window titles.
+const BROWSER_TITLE_SUFFIXES: &[(&str, &str, &[&str])] = &[
+    ("chrome.exe", "Chrome", &[" - Google Chrome"]),
+    ("brave.exe", "Brave", &[" - Brave"]),
+    ("msedge.exe", "Edge", &[" - Microsoft\u{200b} Edge", " - Microsoft Edge"]),
+    ("firefox.exe", "Firefox", &[" — Mozilla Firefox", " - Mozilla Firefox"]),
+];
+
+struct RecentVisit {
+    title: String,
+    url: String,
+    visit_timestamp: i64,
+}
+
+#[derive(Default)]
+struct BrowserVisits {
+    /// When the last history read started; `None` until the first one.
+    refreshed_at: Option<Instant>,
+    visits: Vec<RecentVisit>,
+}
+
+#[derive(Serialize)]
+pub struct UrlTime {
+    url: String,
+    time_seconds: i64,
+}
+
+#[derive(Serialize)]
+pub struct SiteTimeSummary {
+    date: String,
+    domain: String,
+    category: String,
+    time_seconds: i64,
+    time: String,
+    urls: Vec<UrlTime>,
+}
+
+lazy_static::lazy_static! {
+    /// Foreground milliseconds not yet written to `ems_data.db`, keyed by (local date, domain, URL).
+    /// The URL is empty for time on pages that could not be matched.
+    static ref PENDING_TIME: Mutex<BTreeMap<(String, String, String), i64>> = Mutex::new(BTreeMap::new());
+    /// Visits of the last `RECENT_VISIT_HOURS` per browser.
+    static ref RECENT_VISITS: Mutex<HashMap<&'static str, BrowserVisits>> = Mutex::new(HashMap::new());
+    static ref TAB_COUNT_SUFFIX: Regex = Regex::new(r"\s+and \d+ more pages?$").unwrap();
+}
+
+/// Returns the browser name and the page title with the browser suffix removed.
+pub(crate) fn strip_browser_suffix(process_name: &str, window_title: &str) -> Option<(&'static str, String)> {
+    let (_, browser, suffixes) = BROWSER_TITLE_SUFFIXES
+        .iter()
+        .find(|(exe, _, _)| exe.eq_ignore_ascii_case(process_name))?;
+
+    let title = suffixes
+        .iter()
+        .find_map(|suffix| window_title.strip_suffix(suffix))
+        .unwrap_or(window_title);
+    // Edge appends "and N more pages" when tabs are grouped.
+    let title = TAB_COUNT_SUFFIX.replace(title, "").trim().to_string();
+    Some((browser, title))
+}
+
+pub(crate) fn ensure_table(conn: &Connection) {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS time_on_site (
+            date TEXT NOT NULL,
+            domain TEXT NOT NULL,
+            url TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            PRIMARY KEY (date, domain, url)
+        )",
+        [],
+    ).expect("Failed to create time_on_site table");
+}
+
+/// Re-reads the focused browser's history once it is stale. The read runs on its own thread,
+/// without holding the cache, because copying the History database can take seconds. After the
+/// first read only visits from the newest cached second onwards are queried.
+fn refresh_recent_visits(browser: &'static str) {
+    let since = {
+        let mut cache = RECENT_VISITS.lock().unwrap();
+        let entry = cache.entry(browser).or_default();
+        if entry.refreshed_at.is_some_and(|at| at.elapsed().as_secs() < HISTORY_REFRESH_SECS) {
+            return;
+        }
+        // Claimed before reading, so samples taken meanwhile do not start a second read
+        entry.refreshed_at = Some(Instant::now());
+
+        let window_start = (Utc::now() - Duration::hours(RECENT_VISIT_HOURS)).timestamp();
+        entry.visits.retain(|visit| visit.visit_timestamp >= window_start);
+        entry.visits.iter().map(|visit| visit.visit_timestamp).max().unwrap_or(window_start)
+    };
+
+    thread::spawn(move || {
+        let history = collect_history(Some(browser), Some(since), 500);
+
+        let mut cache = RECENT_VISITS.lock().unwrap();
+        let entry = cache.entry(browser).or_default();
+        // The newest cached second is read again, so skip the visits from it that are already cached.
+        let known: HashSet<(String, i64)> = entry
+            .visits
+            .iter()
+            .filter(|visit| visit.visit_timestamp >= since)
+            .map(|visit| (visit.url.clone(), visit.visit_timestamp))
+            .collect();
+        let new_visits: Vec<RecentVisit> = history
+            .into_iter()
+            .filter(|visit| !known.contains(&(visit.url.clone(), visit.visit_timestamp)))
+            .map(|visit| RecentVisit {
+                title: visit.title,
+                url: visit.url,
+                visit_timestamp: visit.visit_timestamp,
+            })
+            .collect();
+        entry.visits.extend(new_visits);
+    });
+}
+
+/// Finds the most recent visit whose title matches the page title.
+/// History titles are redacted at ingest, so the window title is redacted the same way first.
+fn match_visit(browser: &str, page_title: &str) -> Option<String> {
+    if page_title.is_empty() {
+        return None;
+    }
+    let page_title = redact_title(page_title).to_lowercase();
+    // Edge shows the profile name as an extra " - Profile" segment.
+    let without_profile = page_title.rsplit_once(" - ").map(|(title, _)| title.to_string());
+
+    let cache = RECENT_VISITS.lock().unwrap();
+    let candidates = cache.get(browser)?.visits.iter().filter(|visit| !visit.title.is_empty());
+
+    let best = |matches: &dyn Fn(&str) -> bool| {
+        candidates
+            .clone()
+            .filter(|visit| matches(&visit.title.to_lowercase()))
+            .max_by_key(|visit| visit.visit_timestamp)
+            .map(|visit| visit.url.clone())
+    };
+
+    best(&|title| title == page_title)
+        .or_else(|| without_profile.as_ref().and_then(|trimmed| best(&|title| title == trimmed.as_str())))
+        .or_else(|| best(&|title| title.starts_with(&page_title) || page_title.starts_with(title)))
+}
+
+/// Credits `millis` of foreground time to the page, or to `unknown` when it was not matched.
+fn record_foreground_time(url: Option<String>, millis: i64) {
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    let (domain, url) = match url {
+        Some(url) => (split_url(&url).map(|(host, _)| host).unwrap_or_else(|| "unknown".to_string()), url),
+        None => ("unknown".to_string(), String::new()),
+    };
+    *PENDING_TIME.lock().unwrap().entry((date, domain, url)).or_insert(0) += millis;
+}
+
+/// Adds the pending foreground time to the `time_on_site` table.
+fn flush_time_on_site() {
+    let pending = std::mem::take(&mut *PENDING_TIME.lock().unwrap());
+    if pending.is_empty() {
+        return;
+    }
+    let conn = connection();
+    for ((date, domain, url), millis) in pending {
+        if let Err(err) = conn.execute(
+            "INSERT INTO time_on_site (date, domain, url, duration_ms) VALUES (?, ?, ?, ?)
+             ON CONFLICT (date, domain, url) DO UPDATE SET duration_ms = duration_ms + excluded.duration_ms",
+            params![date, domain, url, millis],
+        ) {
+            eprintln!("Failed to store time on site: {}", err);
+        }
+    }
+}
+
+/// Samples the foreground window every second and attributes browser time to the matching URL.
+/// Each sample is credited with the time since the previous one, so a slow sample is not undercounted.
+pub fn start_active_tab_tracker() {
+    thread::spawn(|| {
+        let mut last_sample = Instant::now();
+        let mut last_flush = Instant::now();
+        loop {
+            thread::sleep(std::time::Duration::from_secs(1));
+            let elapsed_ms = (last_sample.elapsed().as_millis() as i64).min(MAX_SAMPLE_GAP_MS);
+            last_sample = Instant::now();
+            if last_flush.elapsed().as_secs() >= FLUSH_SECS {
+                flush_time_on_site();
+                last_flush = Instant::now();
+            }
+            sample_foreground(elapsed_ms);
+        }
+    });
+}
+
+/// Attributes one sample, covering `elapsed_ms`, to the page in the foreground.
+fn sample_foreground(elapsed_ms: i64) {
+    if is_afk() {
+        return;
+    }
+    let Some(window) = get_foreground_window() else {
+        track_private_foreground(None);
+        return;
+    };
+
+    // Private windows only count towards private-browsing time and are never matched to history.
+    let private_browser = detect_private_window(&window.process_name, &window.title);
+    track_private_foreground(private_browser);
+    if private_browser.is_some() {
+        return;
+    }
+
+    let Some((browser, page_title)) = strip_browser_suffix(&window.process_name, &window.title) else {
+        return;
+    };
+
+    refresh_recent_visits(browser);
+    let url = match_visit(browser, &page_title);
+    if let Some(url) = &url {
+        on_site_visit(url);
+    }
+    record_foreground_time(url, elapsed_ms);
+}
+
+/// Returns foreground time per domain (and per URL) for `date` (`YYYY-MM-DD`, default today).
+/// Time the browser was focused on a page that could not be matched is reported as `unknown`.
+#[tauri::command]
+pub fn get_time_on_site(date: Option<String>) -> String {
+    let date = date.unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string());
+    flush_time_on_site();
+
+    let conn = connection();
+    let mut stmt = conn.prepare(
+        "SELECT domain, url, duration_ms FROM time_on_site WHERE date = ? ORDER BY duration_ms DESC"
+    ).expect("Failed to prepare query");
+    let rows: Vec<(String, String, i64)> = stmt
+        .query_map([&date], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    let mut domains: BTreeMap<String, (i64, Vec<UrlTime>)> = BTreeMap::new();
+    for (domain, url, millis) in rows {
+        let (total_ms, urls) = domains.entry(domain).or_default();
+        *total_ms += millis;
+        if !url.is_empty() {
+            urls.push(UrlTime { url, time_seconds: millis / 1000 });
+        }
+    }
+
+    let mut summary: Vec<SiteTimeSummary> = domains
+        .into_iter()
+        .map(|(domain, (total_ms, urls))| SiteTimeSummary {
+            date: date.clone(),
+            category: categorize_url(&format!("https://{}/", domain)),
+            domain,
+            time_seconds: total_ms / 1000,
+            time: format_duration(total_ms / 1000),
+            urls,
+        })
+        .collect();
+    summary.sort_by_key(|site| std::cmp::Reverse(site.time_seconds));
+
+    serde_json::to_string(&summary).unwrap_or_else(|_| "[]".to_string())
+}
@@ -4,6 +4,10 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tauri::command;
+use super::screenshot_triggers::{fire_trigger, CaptureTrigger};
+use windows::Win32::System::StationsAndDesktops::{
+    CloseDesktop, OpenInputDesktop, SwitchDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_SWITCHDESKTOP
+};
 use windows::Win32::System::SystemInformation::GetTickCount64;
 use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
 
@@ -78,6 +82,7 @@ pub fn start_afk_tracker() {
                     state.afk_end.unwrap(),
                     duration
                 );
+                fire_trigger(CaptureTrigger::AfkReturn { away_secs: duration.num_seconds() });
     
                 // 🛠 Reset AFK state
                 state.is_afk = false;
@@ -99,6 +104,8 @@ pub fn start_afk_tracker() {
         } 
         // 🔄 If AFK but idle time resets, user has returned!
         else if state.is_afk && idle_time < idle_threshold {
+            let away_secs = state.afk_start.map(|start| (Local::now() - start).num_seconds()).unwrap_or(0);
+            fire_trigger(CaptureTrigger::AfkReturn { away_secs });
             state.is_afk = false;
             state.afk_start = None;
             state.afk_end = None;
@@ -114,6 +121,26 @@ pub fn start_afk_tracker() {
     });
     }
 
+/// Returns whether the user is currently marked as away.
+pub fn is_afk() -> bool {
+    AFK_STATE.lock().map(|state| state.is_afk).unwrap_or(false)
+}
+
+/// Returns whether the workstation is locked. While locked the input desktop is the
+/// secure desktop, which cannot be opened or switched to from the user's session.
+pub fn is_session_locked() -> bool {
+    unsafe {
+        match OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_SWITCHDESKTOP) {
+            Ok(desktop) => {
+                let locked = SwitchDesktop(desktop).is_err();
+                let _ = CloseDesktop(desktop);
+                locked
+            }
+            Err(_) => true,
+        }
+    }
+}
+
 #[command]
 pub fn get_afk_status() -> AfkData {
     let state = AFK_STATE.lock().unwrap();
@@ -1,56 +1,125 @@
-use rusqlite::{Connection, Result};
 use serde::{Serialize, Deserialize};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde_json::Value;
 use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Semaphore;
+use tokio::task::{self, JoinSet};
+use tokio::time::timeout;
+use super::browser_categories::categorize_url;
+use super::browser_redaction::{redact_title, redact_url};
+use super::browser_snapshot::snapshot_database;
 
 #[derive(Serialize, Deserialize)]
 pub struct BrowserHistory {
-    profile: String,
-    browser: String,
-    profile_display_name: String,
-    gmail: String,
-    title: String,
-    url: String,
-    visit_time: String,
-}
-
-fn get_profile_display_names(browser_name: &str) -> HashMap<String, String> {
-    let user_profile = env::var("USERPROFILE").unwrap_or_else(|_| ".".to_string());
-    let local_state_path = match browser_name {
-        "Chrome" => PathBuf::from(format!("{}\\AppData\\Local\\Google\\Chrome\\User Data\\Local State", user_profile)),
-        "Brave" => PathBuf::from(format!("{}\\AppData\\Local\\BraveSoftware\\Brave-Browser\\User Data\\Local State", user_profile)),
-        "Edge" => PathBuf::from(format!("{}\\AppData\\Local\\Microsoft\\Edge\\User Data\\Local State", user_profile)),
-        _ => return HashMap::new(),
-    };
+    pub(crate) profile: String,
+    pub(crate) browser: String,
+    pub(crate) profile_display_name: String,
+    pub(crate) gmail: String,
+    pub(crate) title: String,
+    pub(crate) url: String,
+    pub(crate) visit_time: String,
+    pub(crate) visit_timestamp: i64,
+    pub(crate) category: String,
+}
 
-    let mut profile_map = HashMap::new();
-    if let Ok(data) = fs::read_to_string(local_state_path) {
-        if let Ok(json) = serde_json::from_str::<Value>(&data) {
-            if let Some(profiles) = json.get("profile") {
-                if let Some(info_cache) = profiles.get("info_cache") {
-                    if let Some(profiles_map) = info_cache.as_object() {
-                        for (profile_key, profile_info) in profiles_map {
-                            if let Some(display_name) = profile_info.get("name").and_then(Value::as_str) {
-                                profile_map.insert(profile_key.clone(), display_name.to_string());
-                            }
-                        }
-                    }
-                }
-            }
+/// Chromium-based browsers and their `User Data` directories under `USERPROFILE`.
+const CHROMIUM_BROWSERS: &[(&str, &str)] = &[
+    ("Chrome", "\\AppData\\Local\\Google\\Chrome\\User Data"),
+    ("Brave", "\\AppData\\Local\\BraveSoftware\\Brave-Browser\\User Data"),
+    ("Edge", "\\AppData\\Local\\Microsoft\\Edge\\User Data"),
+];
+
+const FIREFOX_PROFILES_PATH: &str = "\\AppData\\Roaming\\Mozilla\\Firefox\\Profiles";
+
+/// Profiles whose history is read at the same time.
+const MAX_PARALLEL_PROFILES: usize = 4;
+
+/// A profile that takes longer than this is reported as timed out.
+const PROFILE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Why a profile's history is missing from `get_browser_history`.
+enum ExtractionError {
+    Failed(String),
+    TimedOut,
+}
+
+impl ExtractionError {
+    /// The `status` reported in `HistoryProgress`.
+    fn status(&self) -> &'static str {
+        match self {
+            ExtractionError::Failed(_) => "failed",
+            ExtractionError::TimedOut => "timed_out",
+        }
+    }
+
+    fn message(self) -> String {
+        match self {
+            ExtractionError::Failed(err) => err,
+            ExtractionError::TimedOut => "Timed out reading history".to_string(),
         }
     }
-    profile_map
 }
 
-fn get_browser_profiles(base_path: &str, browser_name: &str) -> Vec<(PathBuf, String, String)> {
-    let user_profile = env::var("USERPROFILE").unwrap_or_else(|_| ".".to_string());
-    let browser_base_path = PathBuf::from(format!("{}{}", user_profile, base_path));
+/// A profile directory found on disk.
+#[derive(Clone)]
+pub(crate) struct ProfileDir {
+    pub(crate) path: PathBuf,
+    /// Directory name, e.g. `Default` or `Profile 1`.
+    pub(crate) profile: String,
+    pub(crate) display_name: String,
+    pub(crate) browser: String,
+}
+
+/// A signed-in account of a browser profile.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProfileAccount {
+    email: String,
+    full_name: Option<String>,
+    /// Google Workspace domain; `None` for consumer accounts.
+    hosted_domain: Option<String>,
+}
 
-    let profile_display_names = get_profile_display_names(browser_name);
+#[derive(Serialize, Deserialize)]
+pub struct BrowserProfile {
+    browser: String,
+    profile: String,
+    display_name: String,
+    accounts: Vec<ProfileAccount>,
+    hosted_domain: Option<String>,
+    sync_enabled: bool,
+    avatar: Option<String>,
+    managed: bool,
+}
+
+fn user_profile_dir() -> String {
+    env::var("USERPROFILE").unwrap_or_else(|_| ".".to_string())
+}
+
+pub(crate) fn read_json(path: &Path) -> Option<Value> {
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<Value>(&data).ok()
+}
+
+/// Returns `profile.info_cache` from `Local State`, keyed by profile directory name.
+fn get_profile_info_cache(base_path: &str) -> HashMap<String, Value> {
+    let local_state_path = PathBuf::from(format!("{}{}\\Local State", user_profile_dir(), base_path));
+
+    read_json(&local_state_path)
+        .and_then(|json| json.pointer("/profile/info_cache").and_then(Value::as_object).cloned())
+        .map(|info_cache| info_cache.into_iter().collect())
+        .unwrap_or_default()
+}
+
+fn find_chromium_profiles(base_path: &str, browser_name: &str) -> Vec<ProfileDir> {
+    let browser_base_path = PathBuf::from(format!("{}{}", user_profile_dir(), base_path));
+
+    let info_cache = get_profile_info_cache(base_path);
     let mut profiles = Vec::new();
 
     if let Ok(entries) = fs::read_dir(browser_base_path) {
@@ -60,8 +129,18 @@ fn get_browser_profiles(base_path: &str, browser_name: &str) -> Vec<(PathBuf, St
                 let history_db = path.join("History");
                 if history_db.exists() {
                     let profile_name = entry.file_name().to_string_lossy().to_string();
-                    let display_name = profile_display_names.get(&profile_name).cloned().unwrap_or(profile_name.clone());
-                    profiles.push((path, display_name, browser_name.to_string()));
+                    let display_name = info_cache
+                        .get(&profile_name)
+                        .and_then(|info| info.get("name"))
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .unwrap_or(profile_name.clone());
+                    profiles.push(ProfileDir {
+                        path,
+                        profile: profile_name,
+                        display_name,
+                        browser: browser_name.to_string(),
+                    });
                 }
             }
         }
@@ -69,141 +148,333 @@ fn get_browser_profiles(base_path: &str, browser_name: &str) -> Vec<(PathBuf, St
     profiles
 }
 
-fn get_gmail_for_profile(profile_path: &PathBuf) -> String {
-    let preferences_path = profile_path.join("Preferences");
+fn find_firefox_profiles() -> Vec<ProfileDir> {
+    let firefox_base_path = PathBuf::from(format!("{}{}", user_profile_dir(), FIREFOX_PROFILES_PATH));
 
-    if !preferences_path.exists() {
-        return "Unknown".to_string();
+    if let Ok(entries) = fs::read_dir(firefox_base_path) {
+        entries
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                ProfileDir { path: e.path(), profile: name.clone(), display_name: name, browser: "Firefox".to_string() }
+            })
+            .collect()
+    } else {
+        Vec::new()
     }
+}
 
-    if let Ok(data) = fs::read_to_string(&preferences_path) {
-        if let Ok(json) = serde_json::from_str::<Value>(&data) {
-            if let Some(accounts) = json.get("account_info").and_then(Value::as_array) {
-                for account in accounts {
-                    if let Some(email) = account.get("email").and_then(Value::as_str) {
-                        return email.to_string();
-                    }
-                }
-            }
-        }
+/// Finds every Chromium and Firefox profile of the current Windows user.
+pub(crate) fn find_all_profiles() -> Vec<ProfileDir> {
+    let mut profiles: Vec<ProfileDir> = CHROMIUM_BROWSERS
+        .iter()
+        .flat_map(|(browser_name, base_path)| find_chromium_profiles(base_path, browser_name))
+        .collect();
+    profiles.extend(find_firefox_profiles());
+    profiles
+}
+
+fn chromium_accounts(preferences: &Value) -> Vec<ProfileAccount> {
+    preferences
+        .get("account_info")
+        .and_then(Value::as_array)
+        .map(|accounts| {
+            accounts
+                .iter()
+                .filter_map(|account| {
+                    let email = account.get("email").and_then(Value::as_str)?;
+                    Some(ProfileAccount {
+                        email: email.to_string(),
+                        full_name: account.get("full_name").and_then(Value::as_str).map(str::to_string),
+                        hosted_domain: account
+                            .get("hd")
+                            .and_then(Value::as_str)
+                            .filter(|hd| !hd.is_empty() && *hd != "NO_HOSTED_DOMAIN")
+                            .map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads a flag that Chromium stores either as a bool or as 0/1.
+fn json_flag(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(flag)) => *flag,
+        Some(Value::Number(n)) => n.as_i64().unwrap_or(0) != 0,
+        _ => false,
     }
-    "Unknown".to_string()
 }
 
-fn extract_history(profiles: Vec<(PathBuf, String, String)>) -> Vec<BrowserHistory> {
-    let mut all_history = Vec::new();
+fn read_chromium_metadata(dir: &ProfileDir, info: Option<&Value>) -> BrowserProfile {
+    let preferences = read_json(&dir.path.join("Preferences")).unwrap_or(Value::Null);
+    let accounts = chromium_accounts(&preferences);
 
-    for (profile, profile_display_name, browser_name) in profiles {
-        let gmail = get_gmail_for_profile(&profile);
+    let sync_enabled = json_flag(info.and_then(|i| i.get("is_consented_primary_account")))
+        || json_flag(preferences.pointer("/sync/has_setup_completed"));
 
-        let history_path = if browser_name == "Firefox" {
-            profile.join("places.sqlite")
-        } else {
-            profile.join("History")
-        };
+    let avatar = info
+        .and_then(|i| i.get("avatar_icon"))
+        .and_then(Value::as_str)
+        .and_then(|icon| icon.rsplit('/').next())
+        .filter(|icon| !icon.is_empty())
+        .map(str::to_string);
 
-        let temp_path = env::temp_dir().join(format!("{}_history_{}.db", browser_name.to_lowercase(), profile_display_name));
+    let managed = json_flag(info.and_then(|i| i.get("user_accepted_account_management")))
+        || json_flag(info.and_then(|i| i.get("is_managed")))
+        || preferences.get("enterprise_profile_guid").is_some();
 
-        if let Err(err) = fs::copy(&history_path, &temp_path) {
-            eprintln!("Failed to copy history DB for {} profile {}: {}", browser_name, profile_display_name, err);
-            continue;
-        }
+    BrowserProfile {
+        browser: dir.browser.clone(),
+        profile: dir.profile.clone(),
+        display_name: dir.display_name.clone(),
+        hosted_domain: accounts.iter().find_map(|a| a.hosted_domain.clone()),
+        accounts,
+        sync_enabled,
+        avatar,
+        managed,
+    }
+}
 
-        let conn = match Connection::open(&temp_path) {
-            Ok(conn) => conn,
-            Err(err) => {
-                eprintln!("Failed to open history DB for {} profile {}: {}", browser_name, profile_display_name, err);
-                continue;
-            }
-        };
+/// Firefox keeps the signed-in Mozilla account in `signedInUser.json`; sync requires a verified account.
+fn read_firefox_metadata(dir: &ProfileDir) -> BrowserProfile {
+    let signed_in = read_json(&dir.path.join("signedInUser.json"));
+    let account_data = signed_in.as_ref().and_then(|json| json.get("accountData"));
+
+    let accounts: Vec<ProfileAccount> = account_data
+        .and_then(|data| data.get("email"))
+        .and_then(Value::as_str)
+        .map(|email| vec![ProfileAccount { email: email.to_string(), full_name: None, hosted_domain: None }])
+        .unwrap_or_default();
+
+    BrowserProfile {
+        browser: dir.browser.clone(),
+        profile: dir.profile.clone(),
+        display_name: dir.display_name.clone(),
+        accounts,
+        hosted_domain: None,
+        sync_enabled: json_flag(account_data.and_then(|data| data.get("verified"))),
+        avatar: None,
+        managed: false,
+    }
+}
 
-        let query = if browser_name == "Firefox" {
-            "SELECT title, url, visit_date / 1000000 AS visit_time FROM moz_places 
-            JOIN moz_historyvisits ON moz_places.id = moz_historyvisits.place_id 
-            ORDER BY visit_time DESC LIMIT 50"
+pub(crate) fn read_profile_metadata(dir: &ProfileDir) -> BrowserProfile {
+    if dir.browser == "Firefox" {
+        return read_firefox_metadata(dir);
+    }
+
+    let base_path = CHROMIUM_BROWSERS
+        .iter()
+        .find(|(browser_name, _)| *browser_name == dir.browser)
+        .map(|(_, base_path)| *base_path)
+        .unwrap_or_default();
+    let info_cache = get_profile_info_cache(base_path);
+    read_chromium_metadata(dir, info_cache.get(&dir.profile))
+}
+
+impl BrowserProfile {
+    /// The first signed-in account, which is the profile's primary account.
+    pub(crate) fn primary_email(&self) -> String {
+        self.accounts.first().map(|a| a.email.clone()).unwrap_or_else(|| "Unknown".to_string())
+    }
+}
+
+/// Reads the visits of one profile from a snapshot of its history database.
+fn extract_profile_history(dir: ProfileDir, since: Option<i64>, limit: u32) -> Result<Vec<BrowserHistory>, String> {
+    let gmail = read_profile_metadata(&dir).primary_email();
+    let ProfileDir { path: profile, profile: profile_name, display_name: profile_display_name, browser: browser_name } = dir;
+
+    let history_path = if browser_name == "Firefox" {
+        profile.join("places.sqlite")
+    } else {
+        profile.join("History")
+    };
+
+    let snapshot = snapshot_database(&history_path, &format!("{}_{}", browser_name, profile_display_name))
+        .map_err(|err| format!("Failed to snapshot history DB: {}", err))?;
+    let conn = snapshot.connection();
+
+    // Both queries work on individual visits so repeat visits to a URL are counted.
+    let (query, min_visit_time) = if browser_name == "Firefox" {
+        ("SELECT title, url, visit_date / 1000000 AS visit_time FROM moz_places 
+        JOIN moz_historyvisits ON moz_places.id = moz_historyvisits.place_id 
+        WHERE visit_date >= ?1
+        ORDER BY visit_time DESC LIMIT ?2",
+        since.map(|s| s * 1_000_000).unwrap_or(0))
+    } else {
+        ("SELECT urls.title, urls.url, visits.visit_time 
+        FROM visits JOIN urls ON urls.id = visits.url 
+        WHERE visits.visit_time >= ?1 
+        ORDER BY visits.visit_time DESC 
+        LIMIT ?2",
+        since.map(|s| (s + 11_644_473_600) * 1_000_000).unwrap_or(0))
+    };
+
+    let mut stmt = conn.prepare(query).map_err(|err| format!("Failed to prepare query: {}", err))?;
+
+    let history_iter = stmt.query_map([min_visit_time, limit as i64], |row| {
+        let raw_time: i64 = row.get(2)?;
+        let unix_timestamp = if browser_name == "Firefox" {
+            raw_time
         } else {
-            "SELECT title, url, last_visit_time 
-            FROM urls 
-            ORDER BY last_visit_time DESC 
-            LIMIT 50"
+            (raw_time / 1_000_000) - 11_644_473_600
         };
 
-        let mut stmt = match conn.prepare(query) {
-            Ok(stmt) => stmt,
-            Err(err) => {
-                eprintln!("Failed to prepare query for {} profile {}: {}", browser_name, profile_display_name, err);
-                continue;
-            }
-        };
+        let datetime_utc = NaiveDateTime::from_timestamp_opt(unix_timestamp, 0)
+            .map(|dt| DateTime::<Utc>::from_utc(dt, Utc));
 
-        let history_iter = match stmt.query_map([], |row| {
-            let raw_time: i64 = row.get(2)?;
-            let unix_timestamp = if browser_name == "Firefox" {
-                raw_time
-            } else {
-                (raw_time / 1_000_000) - 11_644_473_600
-            };
+        let local_time = datetime_utc
+            .map(|dt| dt.with_timezone(&Local))
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "Unknown Time".to_string());
 
-            let datetime_utc = NaiveDateTime::from_timestamp_opt(unix_timestamp, 0)
-                .map(|dt| DateTime::<Utc>::from_utc(dt, Utc));
-
-            let local_time = datetime_utc
-                .map(|dt| dt.with_timezone(&Local))
-                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-                .unwrap_or_else(|| "Unknown Time".to_string());
-
-            Ok(BrowserHistory {
-                profile: profile_display_name.clone(),
-                browser: browser_name.clone(),
-                profile_display_name: profile_display_name.clone(),
-                gmail: gmail.clone(),
-                title: row.get(0)?,
-                url: row.get(1)?,
-                visit_time: local_time,
-            })
-        }) {
-            Ok(iter) => iter,
-            Err(err) => {
-                eprintln!("Failed to execute query for {} profile {}: {}", browser_name, profile_display_name, err);
-                continue;
-            }
-        };
+        let title: Option<String> = row.get(0)?;
+        let url: String = row.get(1)?;
 
-        for entry in history_iter {
-            match entry {
-                Ok(history) => all_history.push(history),
-                Err(err) => eprintln!("Error reading history entry for {} profile {}: {}", browser_name, profile_display_name, err),
-            }
+        // Categorize on the raw URL, then redact before anything is returned.
+        Ok(BrowserHistory {
+            profile: profile_name.clone(),
+            browser: browser_name.clone(),
+            profile_display_name: profile_display_name.clone(),
+            gmail: gmail.clone(),
+            title: redact_title(&title.unwrap_or_default()),
+            category: categorize_url(&url),
+            url: redact_url(&url),
+            visit_time: local_time,
+            visit_timestamp: unix_timestamp,
+        })
+    }).map_err(|err| format!("Failed to execute query: {}", err))?;
+
+    let mut history = Vec::new();
+    for entry in history_iter {
+        match entry {
+            Ok(visit) => history.push(visit),
+            Err(err) => eprintln!("Error reading history entry for {} profile {}: {}", browser_name, profile_display_name, err),
         }
     }
+    Ok(history)
+}
 
+/// Reads visits from every supported browser profile, or only those of `browser` (e.g. `"Edge"`),
+/// newest first per profile. `since` is a Unix timestamp in seconds; `limit` caps the rows read per profile.
+pub(crate) fn collect_history(browser: Option<&str>, since: Option<i64>, limit: u32) -> Vec<BrowserHistory> {
+    let mut all_history = Vec::new();
+    for dir in find_all_profiles().into_iter().filter(|dir| browser.is_none_or(|browser| dir.browser == browser)) {
+        let (browser_name, profile_display_name) = (dir.browser.clone(), dir.display_name.clone());
+        match extract_profile_history(dir, since, limit) {
+            Ok(history) => all_history.extend(history),
+            Err(err) => eprintln!("{} for {} profile {}", err, browser_name, profile_display_name),
+        }
+    }
     all_history
 }
 
+/// Emitted once per profile while `get_browser_history` runs.
+#[derive(Serialize, Clone)]
+pub struct HistoryProgress {
+    browser: String,
+    profile: String,
+    display_name: String,
+    /// `done`, `failed` or `timed_out`.
+    status: String,
+    entries: usize,
+    error: Option<String>,
+    completed: usize,
+    total: usize,
+}
+
+#[derive(Serialize)]
+pub struct BrowserHistoryResult {
+    history: Vec<BrowserHistory>,
+    failures: Vec<HistoryProgress>,
+}
+
+/// Extracts every profile concurrently on the blocking pool, at most
+/// `MAX_PARALLEL_PROFILES` at a time, and emits progress as each profile finishes.
+/// A failed or slow profile is reported in `failures` and does not hold back the others.
+///
+/// A timeout only stops waiting for the profile: blocking work cannot be cancelled, so the
+/// read carries on in the background, holding its slot until it finishes, and its result is dropped.
 #[tauri::command]
-pub fn get_browser_history() -> String {
-    let mut all_history = Vec::new();
+pub async fn get_browser_history(app: AppHandle) -> String {
+    let profiles = find_all_profiles();
+    let total = profiles.len();
+    let limiter = Arc::new(Semaphore::new(MAX_PARALLEL_PROFILES));
 
-    let chrome_profiles = get_browser_profiles("\\AppData\\Local\\Google\\Chrome\\User Data", "Chrome");
-    all_history.extend(extract_history(chrome_profiles));
+    let mut tasks = JoinSet::new();
+    for dir in profiles {
+        let limiter = Arc::clone(&limiter);
+        let label = (dir.browser.clone(), dir.profile.clone(), dir.display_name.clone());
+        tasks.spawn(async move {
+            let result = match limiter.acquire_owned().await {
+                Ok(permit) => {
+                    let extraction = task::spawn_blocking(move || {
+                        let _permit = permit;
+                        extract_profile_history(dir, None, 50)
+                    });
+                    match timeout(PROFILE_TIMEOUT, extraction).await {
+                        Ok(Ok(result)) => result.map_err(ExtractionError::Failed),
+                        Ok(Err(join_err)) => Err(ExtractionError::Failed(format!("Extraction panicked: {}", join_err))),
+                        Err(_) => Err(ExtractionError::TimedOut),
+                    }
+                }
+                Err(err) => Err(ExtractionError::Failed(err.to_string())),
+            };
+            (label, result)
+        });
+    }
 
-    let brave_profiles = get_browser_profiles("\\AppData\\Local\\BraveSoftware\\Brave-Browser\\User Data", "Brave");
-    all_history.extend(extract_history(brave_profiles));
+    let mut history = Vec::new();
+    let mut failures = Vec::new();
+    let mut completed = 0;
+    while let Some(joined) = tasks.join_next().await {
+        completed += 1;
+        let ((browser, profile, display_name), result) = match joined {
+            Ok(joined) => joined,
+            Err(err) => {
+                eprintln!("History extraction task failed: {}", err);
+                continue;
+            }
+        };
 
-    let edge_profiles = get_browser_profiles("\\AppData\\Local\\Microsoft\\Edge\\User Data", "Edge");
-    all_history.extend(extract_history(edge_profiles));
+        let (status, entries, error) = match result {
+            Ok(profile_history) => {
+                let entries = profile_history.len();
+                history.extend(profile_history);
+                ("done", entries, None)
+            }
+            Err(err) => (err.status(), 0, Some(err.message())),
+        };
 
-    let firefox_base_path = PathBuf::from(format!(
-        "{}\\AppData\\Roaming\\Mozilla\\Firefox\\Profiles",
-        env::var("USERPROFILE").unwrap_or_else(|_| ".".to_string())
-    ));
+        let progress = HistoryProgress {
+            browser,
+            profile,
+            display_name,
+            status: status.to_string(),
+            entries,
+            error,
+            completed,
+            total,
+        };
+        if let Err(err) = app.emit("browser-history-progress", progress.clone()) {
+            eprintln!("Failed to emit history progress: {}", err);
+        }
+        if progress.error.is_some() {
+            eprintln!("History extraction {} for {} profile {}", progress.status, progress.browser, progress.display_name);
+            failures.push(progress);
+        }
+    }
 
-    let firefox_profiles = if let Ok(entries) = fs::read_dir(firefox_base_path) {
-        entries.filter_map(|entry| entry.ok().map(|e| (e.path(), e.file_name().to_string_lossy().to_string(), "Firefox".to_string()))).collect()
-    } else {
-        Vec::new()
-    };
-    all_history.extend(extract_history(firefox_profiles));
+    let result = BrowserHistoryResult { history, failures };
+    serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()) // Convert to JSON
+}
 
-    serde_json::to_string(&all_history).unwrap_or_else(|_| "[]".to_string()) // Convert to JSON
+/// Lists every browser profile with its signed-in accounts, sync, avatar and management state.
+#[tauri::command]
+pub fn get_browser_profiles() -> String {
+    let profiles: Vec<BrowserProfile> = find_all_profiles().iter().map(read_profile_metadata).collect();
+    serde_json::to_string(&profiles).unwrap_or_else(|_| "[]".to_string())
 }
@@ -0,0 +1,228 @@
+use chrono::{Duration, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::utils::file::load_json_config;
+use crate::utils::time::format_duration;
+use super::browser::collect_history;
+
+const CATEGORY_RULES_FILE: &str = "domain_categories.json";
+
+/// Category reported for URLs that no rule matches.
+const UNCATEGORIZED: &str = "uncategorized";
+
+/// Gaps between visits longer than this are treated as idle time, not time on the page.
+const MAX_VISIT_SECONDS: i64 = 300;
+
+/// Built-in rules, evaluated after the rules file. Order matters: the first match wins.
+const DEFAULT_RULES: &[(&str, &str)] = &[
+    ("*.atlassian.net/wiki/*", "documentation"),
+    ("docs.rs", "documentation"),
+    ("doc.rust-lang.org", "documentation"),
+    ("developer.mozilla.org", "documentation"),
+    ("learn.microsoft.com", "documentation"),
+    (".readthedocs.io", "documentation"),
+    ("stackoverflow.com", "documentation"),
+    (".stackexchange.com", "documentation"),
+    (".atlassian.net", "work_tools"),
+    (".github.com", "work_tools"),
+    (".gitlab.com", "work_tools"),
+    (".slack.com", "work_tools"),
+    ("teams.microsoft.com", "work_tools"),
+    ("outlook.office.com", "work_tools"),
+    ("mail.google.com", "work_tools"),
+    ("docs.google.com", "work_tools"),
+    ("drive.google.com", "work_tools"),
+    ("meet.google.com", "work_tools"),
+    (".zoom.us", "work_tools"),
+    (".notion.so", "work_tools"),
+    (".figma.com", "work_tools"),
+    (".trello.com", "work_tools"),
+    (".asana.com", "work_tools"),
+    (".facebook.com", "social"),
+    (".instagram.com", "social"),
+    (".twitter.com", "social"),
+    ("x.com", "social"),
+    (".linkedin.com", "social"),
+    (".reddit.com", "social"),
+    (".tiktok.com", "social"),
+    ("web.whatsapp.com", "social"),
+    ("news.google.com", "news"),
+    (".bbc.com", "news"),
+    (".bbc.co.uk", "news"),
+    (".cnn.com", "news"),
+    (".nytimes.com", "news"),
+    (".theguardian.com", "news"),
+    (".reuters.com", "news"),
+    (".ndtv.com", "news"),
+    (".hindustantimes.com", "news"),
+    (".indiatimes.com", "news"),
+    (".youtube.com", "streaming"),
+    ("youtu.be", "streaming"),
+    (".netflix.com", "streaming"),
+    (".primevideo.com", "streaming"),
+    (".hotstar.com", "streaming"),
+    (".twitch.tv", "streaming"),
+    (".spotify.com", "streaming"),
+    (".amazon.com", "shopping"),
+    (".amazon.in", "shopping"),
+    (".flipkart.com", "shopping"),
+    (".myntra.com", "shopping"),
+    (".ebay.com", "shopping"),
+];
+
+/// A single rule from the rules file.
+///
+/// The host part of `pattern` supports three forms:
+/// - `example.com` matches that host only,
+/// - `.example.com` matches the domain and all of its subdomains (suffix match),
+/// - any `*` is a wildcard, e.g. `*.example.com` or `mail.google.*`.
+///
+/// An optional path part (`example.com/wiki/*`) is matched the same way against the URL path.
+#[derive(Clone, Deserialize)]
+pub struct CategoryRule {
+    pattern: String,
+    category: String,
+}
+
+#[derive(Default, Deserialize)]
+struct CategoryRulesFile {
+    #[serde(default)]
+    rules: Vec<CategoryRule>,
+}
+
+#[derive(Serialize)]
+pub struct CategorySummary {
+    date: String,
+    category: String,
+    visits: u32,
+    time_seconds: i64,
+    time: String,
+}
+
+lazy_static::lazy_static! {
+    static ref CATEGORY_RULES: Vec<CategoryRule> = load_rules();
+}
+
+fn load_rules() -> Vec<CategoryRule> {
+    let mut rules = load_json_config::<CategoryRulesFile>(CATEGORY_RULES_FILE).rules;
+    rules.extend(DEFAULT_RULES.iter().map(|(pattern, category)| CategoryRule {
+        pattern: pattern.to_string(),
+        category: category.to_string(),
+    }));
+    rules
+}
+
+/// Matches `text` against a pattern where `*` stands for any run of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let first = parts[0];
+    let last = parts[parts.len() - 1];
+    if !text.starts_with(first) || text.len() < first.len() + last.len() || !text.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+pub(crate) fn host_matches(pattern: &str, host: &str) -> bool {
+    if let Some(domain) = pattern.strip_prefix('.') {
+        host == domain || host.ends_with(pattern)
+    } else {
+        wildcard_match(pattern, host)
+    }
+}
+
+impl CategoryRule {
+    fn matches(&self, host: &str, path: &str) -> bool {
+        let pattern = self.pattern.to_lowercase();
+        match pattern.split_once('/') {
+            Some((host_pattern, path_pattern)) => {
+                host_matches(host_pattern, host) && wildcard_match(&format!("/{}", path_pattern), path)
+            }
+            None => host_matches(&pattern, host),
+        }
+    }
+}
+
+/// Splits a URL into a lowercase host (without `www.`) and its path.
+pub(crate) fn split_url(url: &str) -> Option<(String, String)> {
+    let rest = url.split_once("://").map(|(_, rest)| rest)?;
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let (authority, path) = match rest.find('/') {
+        Some(pos) => (&rest[..pos], &rest[pos..]),
+        None => (rest, "/"),
+    };
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host).to_lowercase();
+    let host = host.strip_prefix("www.").map(str::to_string).unwrap_or(host);
+    Some((host, path.to_string()))
+}
+
+/// Returns the category of the first rule matching `url`, or `uncategorized`.
+pub fn categorize_url(url: &str) -> String {
+    let Some((host, path)) = split_url(url) else {
+        return UNCATEGORIZED.to_string();
+    };
+
+    CATEGORY_RULES
+        .iter()
+        .find(|rule| rule.matches(&host, &path))
+        .map(|rule| rule.category.clone())
+        .unwrap_or_else(|| UNCATEGORIZED.to_string())
+}
+
+/// Summarises visits and estimated time per category for each of the last `days` days (default 1).
+/// Time on a visit is the gap until the next visit in the same profile, capped at five minutes.
+#[tauri::command]
+pub fn get_browsing_category_summary(days: Option<u32>) -> String {
+    let days = days.unwrap_or(1).max(1) as i64;
+    let start_of_day = Local::now().date_naive() - Duration::days(days - 1);
+    let since = Local
+        .from_local_datetime(&start_of_day.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .unwrap_or(0);
+
+    let mut history = collect_history(None, Some(since), 10_000);
+    history.sort_by(|a, b| {
+        (&a.browser, &a.profile, a.visit_timestamp).cmp(&(&b.browser, &b.profile, b.visit_timestamp))
+    });
+
+    let mut summary: BTreeMap<(String, String), (u32, i64)> = BTreeMap::new();
+    for (i, visit) in history.iter().enumerate() {
+        let time_on_page = history
+            .get(i + 1)
+            .filter(|next| next.browser == visit.browser && next.profile == visit.profile)
+            .map(|next| (next.visit_timestamp - visit.visit_timestamp).clamp(0, MAX_VISIT_SECONDS))
+            .unwrap_or(0);
+
+        let date = visit.visit_time.get(..10).unwrap_or("Unknown").to_string();
+        let entry = summary.entry((date, visit.category.clone())).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += time_on_page;
+    }
+
+    let rows: Vec<CategorySummary> = summary
+        .into_iter()
+        .map(|((date, category), (visits, time_seconds))| CategorySummary {
+            date,
+            category,
+            visits,
+            time_seconds,
+            time: format_duration(time_seconds),
+        })
+        .collect();
+
+    serde_json::to_string(&rows).unwrap_or_else(|_| "[]".to_string())
+}
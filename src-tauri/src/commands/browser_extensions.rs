@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::utils::file::load_json_config;
+use super::browser::{find_all_profiles, read_json, ProfileDir};
+use super::browser_snapshot::snapshot_database;
+
+const EXTENSION_POLICY_FILE: &str = "extension_policy.json";
+
+/// Extensions not covered by the policy are reported with `approved: false`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ExtensionPolicy {
+    allowed_ids: Vec<String>,
+    /// Treat extensions shipped with the browser (component/built-in) as approved.
+    allow_builtin: bool,
+}
+
+impl Default for ExtensionPolicy {
+    fn default() -> Self {
+        Self { allowed_ids: Vec::new(), allow_builtin: true }
+    }
+}
+
+impl ExtensionPolicy {
+    fn approves(&self, id: &str, install_source: &str) -> bool {
+        (self.allow_builtin && install_source == "builtin")
+            || self.allowed_ids.iter().any(|allowed| allowed.eq_ignore_ascii_case(id))
+    }
+}
+
+#[derive(Serialize)]
+pub struct ExtensionInfo {
+    id: String,
+    name: String,
+    version: String,
+    permissions: Vec<String>,
+    enabled: bool,
+    /// One of `webstore`, `user`, `sideloaded`, `unpacked`, `policy`, `builtin` or `unknown`.
+    install_source: String,
+    approved: bool,
+}
+
+#[derive(Serialize)]
+pub struct ProfileInventory {
+    browser: String,
+    profile: String,
+    display_name: String,
+    bookmark_count: Option<u32>,
+    extensions: Vec<ExtensionInfo>,
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Maps Chromium's `ManifestLocation` values to an install source.
+fn chromium_install_source(location: i64, from_webstore: bool) -> &'static str {
+    match location {
+        1 if from_webstore => "webstore",
+        1 => "user",
+        2 | 3 | 6 => "sideloaded",
+        4 | 8 => "unpacked",
+        5 | 10 => "builtin",
+        7 | 9 => "policy",
+        _ => "unknown",
+    }
+}
+
+/// Resolves `__MSG_name__` placeholders from the extension's default locale.
+fn localize(value: &str, extension_dir: &Path, manifest: &Value) -> String {
+    let Some(key) = value.strip_prefix("__MSG_").and_then(|v| v.strip_suffix("__")) else {
+        return value.to_string();
+    };
+    let locale = manifest.get("default_locale").and_then(Value::as_str).unwrap_or("en");
+    read_json(&extension_dir.join("_locales").join(locale).join("messages.json"))
+        .and_then(|messages| {
+            messages.as_object()?.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).and_then(|(_, entry)| {
+                entry.get("message").and_then(Value::as_str).map(str::to_string)
+            })
+        })
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// Splits a version folder name such as `1.10.0_0` into numbers, so versions compare numerically.
+fn version_key(path: &Path) -> Vec<u64> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().split(['.', '_']).map(|part| part.parse().unwrap_or(0)).collect())
+        .unwrap_or_default()
+}
+
+/// Finds the installed directory of an extension: the `path` from preferences, or the newest version folder.
+fn chromium_extension_dir(profile_path: &Path, id: &str, settings: &Value) -> Option<PathBuf> {
+    if let Some(path) = settings.get("path").and_then(Value::as_str) {
+        let path = PathBuf::from(path);
+        return Some(if path.is_absolute() { path } else { profile_path.join("Extensions").join(path) });
+    }
+
+    fs::read_dir(profile_path.join("Extensions").join(id))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("manifest.json").exists())
+        .max_by_key(|path| version_key(path))
+}
+
+/// Merges `extensions.settings` from `Preferences` and `Secure Preferences` key by key, per
+/// extension: each file holds only some fields (e.g. `state` may be only in `Preferences`).
+/// Where both have a field, `Secure Preferences` wins.
+fn chromium_extension_settings(profile_path: &Path) -> HashMap<String, Value> {
+    let mut settings: HashMap<String, Value> = HashMap::new();
+    for file in ["Preferences", "Secure Preferences"] {
+        let Some(map) = read_json(&profile_path.join(file))
+            .and_then(|json| json.pointer("/extensions/settings").and_then(Value::as_object).cloned())
+        else {
+            continue;
+        };
+        for (id, fields) in map {
+            match (settings.get_mut(&id).and_then(Value::as_object_mut), fields) {
+                (Some(merged), Value::Object(fields)) => merged.extend(fields),
+                (_, fields) => {
+                    settings.insert(id, fields);
+                }
+            }
+        }
+    }
+    settings
+}
+
+fn chromium_extensions(dir: &ProfileDir, policy: &ExtensionPolicy) -> Vec<ExtensionInfo> {
+    let mut extensions = Vec::new();
+
+    for (id, settings) in chromium_extension_settings(&dir.path) {
+        let extension_dir = chromium_extension_dir(&dir.path, &id, &settings);
+        let manifest = extension_dir
+            .as_ref()
+            .and_then(|path| read_json(&path.join("manifest.json")))
+            .or_else(|| settings.get("manifest").cloned())
+            .unwrap_or(Value::Null);
+
+        // Settings without any manifest are leftovers of uninstalled extensions.
+        if manifest.is_null() {
+            continue;
+        }
+
+        let raw_name = manifest.get("name").and_then(Value::as_str).unwrap_or(&id);
+        let name = match &extension_dir {
+            Some(path) => localize(raw_name, path, &manifest),
+            None => raw_name.to_string(),
+        };
+
+        let mut permissions = string_list(manifest.get("permissions"));
+        permissions.extend(string_list(manifest.get("host_permissions")));
+
+        // Newer Chromium drops `state` and only records `disable_reasons` when disabled.
+        let enabled = match settings.get("state").and_then(Value::as_i64) {
+            Some(state) => state == 1,
+            None => match settings.get("disable_reasons") {
+                Some(Value::Array(reasons)) => reasons.is_empty(),
+                Some(Value::Number(reasons)) => reasons.as_i64() == Some(0),
+                _ => true,
+            },
+        };
+
+        let install_source = chromium_install_source(
+            settings.get("location").and_then(Value::as_i64).unwrap_or(0),
+            settings.get("from_webstore").and_then(Value::as_bool).unwrap_or(false),
+        );
+
+        extensions.push(ExtensionInfo {
+            approved: policy.approves(&id, install_source),
+            version: manifest.get("version").and_then(Value::as_str).unwrap_or("").to_string(),
+            id,
+            name,
+            permissions,
+            enabled,
+            install_source: install_source.to_string(),
+        });
+    }
+
+    extensions
+}
+
+fn firefox_install_source(addon: &Value) -> &'static str {
+    let location = addon.get("location").and_then(Value::as_str).unwrap_or("");
+    let source_uri = addon.get("sourceURI").and_then(Value::as_str).unwrap_or("");
+    match location {
+        "app-builtin" | "app-system-defaults" | "app-system-addons" | "app-system-share" => "builtin",
+        "app-profile" if source_uri.contains("addons.mozilla.org") => "webstore",
+        "app-profile" if addon.get("foreignInstall").and_then(Value::as_bool).unwrap_or(false) => "sideloaded",
+        "app-profile" => "user",
+        "app-temporary" => "unpacked",
+        _ => "unknown",
+    }
+}
+
+fn firefox_extensions(dir: &ProfileDir, policy: &ExtensionPolicy) -> Vec<ExtensionInfo> {
+    let Some(json) = read_json(&dir.path.join("extensions.json")) else {
+        return Vec::new();
+    };
+
+    json.get("addons")
+        .and_then(Value::as_array)
+        .map(|addons| {
+            addons
+                .iter()
+                .filter(|addon| addon.get("type").and_then(Value::as_str) == Some("extension"))
+                .filter_map(|addon| {
+                    let id = addon.get("id").and_then(Value::as_str)?.to_string();
+                    let install_source = firefox_install_source(addon);
+
+                    let mut permissions = string_list(addon.pointer("/userPermissions/permissions"));
+                    permissions.extend(string_list(addon.pointer("/userPermissions/origins")));
+
+                    Some(ExtensionInfo {
+                        approved: policy.approves(&id, install_source),
+                        name: addon.pointer("/defaultLocale/name").and_then(Value::as_str).unwrap_or(&id).to_string(),
+                        version: addon.get("version").and_then(Value::as_str).unwrap_or("").to_string(),
+                        enabled: addon.get("active").and_then(Value::as_bool).unwrap_or(false),
+                        install_source: install_source.to_string(),
+                        id,
+                        permissions,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn count_bookmark_urls(node: &Value) -> u32 {
+    let own = (node.get("type").and_then(Value::as_str) == Some("url")) as u32;
+    let children = node
+        .get("children")
+        .and_then(Value::as_array)
+        .map(|children| children.iter().map(count_bookmark_urls).sum())
+        .unwrap_or(0);
+    own + children
+}
+
+fn chromium_bookmark_count(dir: &ProfileDir) -> Option<u32> {
+    let bookmarks = read_json(&dir.path.join("Bookmarks"))?;
+    let roots = bookmarks.get("roots")?.as_object()?;
+    Some(roots.values().map(count_bookmark_urls).sum())
+}
+
+/// Counts Firefox bookmarks (`type = 1`) from a snapshot of `places.sqlite`.
+fn firefox_bookmark_count(dir: &ProfileDir) -> Option<u32> {
+    let snapshot = snapshot_database(&dir.path.join("places.sqlite"), &format!("{}_{}", dir.browser, dir.profile))
+        .map_err(|err| eprintln!("Failed to snapshot bookmarks for {} profile {}: {}", dir.browser, dir.display_name, err))
+        .ok()?;
+    snapshot
+        .connection()
+        .query_row("SELECT COUNT(*) FROM moz_bookmarks WHERE type = 1", [], |row| row.get(0))
+        .ok()
+}
+
+/// Lists installed extensions and bookmark counts per profile, flagging extensions
+/// that are not on the allow-list in `extension_policy.json`.
+#[tauri::command]
+pub fn get_browser_extensions() -> String {
+    let policy: ExtensionPolicy = load_json_config(EXTENSION_POLICY_FILE);
+
+    let inventory: Vec<ProfileInventory> = find_all_profiles()
+        .iter()
+        .map(|dir| {
+            let (extensions, bookmark_count) = if dir.browser == "Firefox" {
+                (firefox_extensions(dir, &policy), firefox_bookmark_count(dir))
+            } else {
+                (chromium_extensions(dir, &policy), chromium_bookmark_count(dir))
+            };
+
+            ProfileInventory {
+                browser: dir.browser.clone(),
+                profile: dir.profile.clone(),
+                display_name: dir.display_name.clone(),
+                bookmark_count,
+                extensions,
+            }
+        })
+        .collect();
+
+    serde_json::to_string(&inventory).unwrap_or_else(|_| "[]".to_string())
+}
@@ -0,0 +1,139 @@
+use regex::Regex;
+use serde::Deserialize;
+use crate::utils::file::load_json_config;
+use super::browser_categories::{host_matches, split_url};
+
+const REDACTION_CONFIG_FILE: &str = "history_redaction.json";
+
+const EMAIL_PATTERN: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+
+/// Runs of four or more digits: account, ticket, phone and OTP numbers.
+const NUMBER_PATTERN: &str = r"\d{4,}";
+
+const REDACTED: &str = "[redacted]";
+
+/// Controls how URLs and titles are scrubbed before they leave `extract_history`.
+/// Credentials embedded in a URL (`user:pass@host`) are always removed.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    strip_query: bool,
+    strip_fragment: bool,
+    /// Domains whose URLs are kept whole, using the same syntax as category rules
+    /// (`example.com`, `.example.com`, `*.example.com`).
+    full_capture_domains: Vec<String>,
+    scrub_emails: bool,
+    scrub_numbers: bool,
+    /// Extra regular expressions removed from titles.
+    title_patterns: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            strip_query: true,
+            strip_fragment: true,
+            full_capture_domains: Vec::new(),
+            scrub_emails: true,
+            scrub_numbers: true,
+            title_patterns: Vec::new(),
+        }
+    }
+}
+
+struct Redactor {
+    config: RedactionConfig,
+    title_regexes: Vec<Regex>,
+}
+
+lazy_static::lazy_static! {
+    static ref REDACTOR: Redactor = Redactor::new(load_json_config(REDACTION_CONFIG_FILE));
+}
+
+impl Redactor {
+    fn new(config: RedactionConfig) -> Self {
+        let mut patterns: Vec<&str> = Vec::new();
+        if config.scrub_emails {
+            patterns.push(EMAIL_PATTERN);
+        }
+        if config.scrub_numbers {
+            patterns.push(NUMBER_PATTERN);
+        }
+        patterns.extend(config.title_patterns.iter().map(String::as_str));
+
+        let title_regexes = patterns
+            .into_iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    eprintln!("Ignoring invalid title redaction pattern {}: {}", pattern, err);
+                    None
+                }
+            })
+            .collect();
+
+        Self { config, title_regexes }
+    }
+
+    fn is_full_capture(&self, url: &str) -> bool {
+        split_url(url)
+            .map(|(host, _)| {
+                self.config
+                    .full_capture_domains
+                    .iter()
+                    .any(|pattern| host_matches(&pattern.to_lowercase(), &host))
+            })
+            .unwrap_or(false)
+    }
+
+    fn redact_url(&self, url: &str) -> String {
+        let Some((scheme, rest)) = url.split_once("://") else {
+            return url.to_string();
+        };
+
+        // Drop `user:password@` from the authority.
+        let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let (authority, tail) = rest.split_at(authority_end);
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+
+        if self.is_full_capture(url) {
+            return format!("{}://{}{}", scheme, host, tail);
+        }
+
+        let (tail, fragment) = match tail.split_once('#') {
+            Some((before, fragment)) => (before, Some(fragment)),
+            None => (tail, None),
+        };
+        let (path, query) = match tail.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (tail, None),
+        };
+
+        let mut redacted = format!("{}://{}{}", scheme, host, path);
+        if let Some(query) = query.filter(|_| !self.config.strip_query) {
+            redacted.push('?');
+            redacted.push_str(query);
+        }
+        if let Some(fragment) = fragment.filter(|_| !self.config.strip_fragment) {
+            redacted.push('#');
+            redacted.push_str(fragment);
+        }
+        redacted
+    }
+
+    fn redact_title(&self, title: &str) -> String {
+        self.title_regexes
+            .iter()
+            .fold(title.to_string(), |title, regex| regex.replace_all(&title, REDACTED).into_owned())
+    }
+}
+
+/// Removes credentials and, unless the domain is allow-listed, the query string and fragment.
+pub fn redact_url(url: &str) -> String {
+    REDACTOR.redact_url(url)
+}
+
+/// Scrubs emails, long numbers and any configured patterns from a page title.
+pub fn redact_title(title: &str) -> String {
+    REDACTOR.redact_title(title)
+}
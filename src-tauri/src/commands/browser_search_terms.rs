@@ -0,0 +1,309 @@
+use chrono::{Duration, Local, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::task;
+use crate::utils::db::connection;
+use crate::utils::time::local_date_range;
+use super::browser::{find_all_profiles, ProfileDir};
+use super::browser_categories::{host_matches, split_url};
+use super::browser_redaction::redact_title;
+use super::browser_snapshot::snapshot_database;
+
+/// How far back each sync looks; rows already stored are skipped.
+const SYNC_WINDOW_DAYS: i64 = 30;
+
+/// Browser histories are copied and re-read at most this often.
+const SYNC_INTERVAL_SECS: u64 = 300;
+
+/// Search pages recognised in Firefox history: (host pattern, path, query parameter, engine).
+/// Host patterns use the same syntax as the category rules; the engine names are also used for
+/// Chromium keyword searches.
+const SEARCH_URL_PATTERNS: &[(&str, &str, &str, &str)] = &[
+    ("google.*", "/search", "q", "Google"),
+    ("*.google.*", "/search", "q", "Google"),
+    (".bing.com", "/search", "q", "Bing"),
+    (".duckduckgo.com", "/", "q", "DuckDuckGo"),
+    ("*", "/wiki/search", "text", "Confluence"),
+    ("*", "/dosearchsite.action", "queryString", "Confluence"),
+    ("*", "/issues/", "jql", "Jira"),
+    ("*", "/secure/QuickSearch.jspa", "searchString", "Jira"),
+];
+
+lazy_static::lazy_static! {
+    /// When the last sync started; `None` until the first one.
+    static ref LAST_SYNC: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+struct SearchTerm {
+    browser: String,
+    profile: String,
+    engine: String,
+    term: String,
+    searched_ts: i64,
+}
+
+#[derive(Serialize)]
+pub struct SearchTermEntry {
+    browser: String,
+    profile: String,
+    engine: String,
+    term: String,
+    searched_at: String,
+}
+
+#[derive(Serialize)]
+pub struct TopSearch {
+    term: String,
+    count: i64,
+    last_searched_at: String,
+}
+
+pub(crate) fn ensure_table(conn: &Connection) {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS search_terms (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            browser TEXT NOT NULL,
+            profile TEXT NOT NULL,
+            engine TEXT NOT NULL,
+            term TEXT NOT NULL,
+            searched_at TEXT NOT NULL,
+            searched_ts INTEGER NOT NULL,
+            UNIQUE (browser, profile, term, searched_ts)
+        )",
+        [],
+    ).expect("Failed to create search_terms table");
+}
+
+/// Decodes `application/x-www-form-urlencoded` text (`+` and `%XX`).
+fn decode_query_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Returns the engine and search term when `url` is a known search results page.
+fn search_term_from_url(url: &str) -> Option<(&'static str, String)> {
+    let (host, path) = split_url(url)?;
+    let query = url.split_once('?')?.1.split('#').next().unwrap_or("");
+
+    SEARCH_URL_PATTERNS.iter().find_map(|(host_pattern, search_path, param, engine)| {
+        if !host_matches(host_pattern, &host) || !path.starts_with(search_path) {
+            return None;
+        }
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| key == param)
+            .map(|(_, value)| decode_query_value(value).trim().to_string())
+            .filter(|term| !term.is_empty())
+            .map(|term| (*engine, term))
+    })
+}
+
+/// Names the engine of a Chromium keyword search like its Firefox counterpart, e.g. `www.google.com`
+/// becomes `Google`. Other hosts use their capitalised domain label: `search.brave.com` is `Brave`.
+fn engine_name(host: &str) -> String {
+    let known = SEARCH_URL_PATTERNS
+        .iter()
+        .find(|(host_pattern, ..)| *host_pattern != "*" && host_matches(host_pattern, host));
+    if let Some((_, _, _, engine)) = known {
+        return engine.to_string();
+    }
+
+    let labels: Vec<&str> = host.trim_start_matches("www.").split('.').collect();
+    // Skip short second-level labels such as the `co` in `bbc.co.uk`
+    let label = match labels.len() {
+        0 | 1 => labels.first().copied().unwrap_or(host),
+        len if len > 2 && labels[len - 2].len() <= 3 => labels[len - 3],
+        len => labels[len - 2],
+    };
+    let mut chars = label.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Reads search terms of one profile: Chromium's `keyword_search_terms` table, or
+/// search result URLs from Firefox history.
+fn extract_search_terms(dir: &ProfileDir, since: i64) -> Result<Vec<SearchTerm>, String> {
+    let is_firefox = dir.browser == "Firefox";
+    let db_path = dir.path.join(if is_firefox { "places.sqlite" } else { "History" });
+    let snapshot = snapshot_database(&db_path, &format!("{}_{}_search", dir.browser, dir.profile))?;
+    let conn = snapshot.connection();
+
+    // (keyword term, url, unix time); Firefox has no keyword table, so the term comes from the URL.
+    let rows: Vec<(Option<String>, String, i64)> = if is_firefox {
+        let mut stmt = conn
+            .prepare(
+                "SELECT NULL, moz_places.url, moz_historyvisits.visit_date / 1000000
+                 FROM moz_places JOIN moz_historyvisits ON moz_places.id = moz_historyvisits.place_id
+                 WHERE moz_historyvisits.visit_date >= ?1 AND moz_places.url LIKE '%?%'",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([since * 1_000_000], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(Result::ok).collect()
+    } else {
+        // One row per visit of the results page, like the Firefox query.
+        let mut stmt = conn
+            .prepare(
+                "SELECT keyword_search_terms.term, urls.url, visits.visit_time / 1000000 - 11644473600
+                 FROM keyword_search_terms
+                 JOIN urls ON urls.id = keyword_search_terms.url_id
+                 JOIN visits ON visits.url = urls.id
+                 WHERE visits.visit_time >= ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([(since + 11_644_473_600) * 1_000_000], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(Result::ok).collect()
+    };
+
+    let terms = rows
+        .into_iter()
+        .filter_map(|(term, url, searched_ts)| {
+            let (engine, term) = match term {
+                Some(term) => (split_url(&url).map(|(host, _)| engine_name(&host)).unwrap_or_else(|| "unknown".to_string()), term),
+                None => {
+                    let (engine, term) = search_term_from_url(&url)?;
+                    (engine.to_string(), term)
+                }
+            };
+            Some(SearchTerm {
+                browser: dir.browser.clone(),
+                profile: dir.profile.clone(),
+                engine,
+                // Search terms follow the same scrubbing rules as page titles.
+                term: redact_title(&term),
+                searched_ts,
+            })
+        })
+        .collect();
+
+    Ok(terms)
+}
+
+fn format_local(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "Unknown Time".to_string())
+}
+
+/// Pulls search terms from every profile into the `search_terms` table, at most once per
+/// `SYNC_INTERVAL_SECS`. Callers arriving during a sync wait for it and then skip their own.
+fn sync_search_terms() {
+    let mut last_sync = LAST_SYNC.lock().unwrap();
+    if last_sync.is_some_and(|at| at.elapsed().as_secs() < SYNC_INTERVAL_SECS) {
+        return;
+    }
+    *last_sync = Some(Instant::now());
+
+    let since = (Utc::now() - Duration::days(SYNC_WINDOW_DAYS)).timestamp();
+    let mut terms = Vec::new();
+    for dir in find_all_profiles() {
+        match extract_search_terms(&dir, since) {
+            Ok(profile_terms) => terms.extend(profile_terms),
+            Err(err) => eprintln!("Failed to read search terms for {} profile {}: {}", dir.browser, dir.display_name, err),
+        }
+    }
+
+    let conn = connection();
+    for term in terms {
+        if let Err(err) = conn.execute(
+            "INSERT OR IGNORE INTO search_terms (browser, profile, engine, term, searched_at, searched_ts)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![term.browser, term.profile, term.engine, term.term, format_local(term.searched_ts), term.searched_ts],
+        ) {
+            eprintln!("Failed to store search term: {}", err);
+        }
+    }
+}
+
+/// Lists search terms between `from` and `to` (inclusive `YYYY-MM-DD` dates), newest first.
+/// Syncs from every profile first when the last sync is stale, on the blocking pool.
+#[tauri::command]
+pub async fn get_search_terms(from: Option<String>, to: Option<String>) -> String {
+    task::spawn_blocking(move || search_terms_between(from, to))
+        .await
+        .unwrap_or_else(|_| "[]".to_string())
+}
+
+fn search_terms_between(from: Option<String>, to: Option<String>) -> String {
+    sync_search_terms();
+    let (from_ts, to_ts) = local_date_range(from, to);
+
+    let conn = connection();
+    let mut stmt = conn.prepare(
+        "SELECT browser, profile, engine, term, searched_at FROM search_terms
+         WHERE searched_ts >= ? AND searched_ts < ? ORDER BY searched_ts DESC"
+    ).expect("Failed to prepare query");
+
+    let entries: Vec<SearchTermEntry> = stmt
+        .query_map([from_ts, to_ts], |row| {
+            Ok(SearchTermEntry {
+                browser: row.get(0)?,
+                profile: row.get(1)?,
+                engine: row.get(2)?,
+                term: row.get(3)?,
+                searched_at: row.get(4)?,
+            })
+        })
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Returns the most frequent search terms of the last `days` days (default 7).
+/// Syncs from every profile first when the last sync is stale, on the blocking pool.
+#[tauri::command]
+pub async fn get_top_searches(days: Option<u32>, limit: Option<u32>) -> String {
+    task::spawn_blocking(move || top_searches(days, limit))
+        .await
+        .unwrap_or_else(|_| "[]".to_string())
+}
+
+fn top_searches(days: Option<u32>, limit: Option<u32>) -> String {
+    sync_search_terms();
+    let since = (Utc::now() - Duration::days(days.unwrap_or(7) as i64)).timestamp();
+
+    let conn = connection();
+    let mut stmt = conn.prepare(
+        "SELECT lower(term) AS normalized, COUNT(*) AS hits, MAX(searched_at) FROM search_terms
+         WHERE searched_ts >= ? GROUP BY normalized ORDER BY hits DESC LIMIT ?"
+    ).expect("Failed to prepare query");
+
+    let top: Vec<TopSearch> = stmt
+        .query_map(params![since, limit.unwrap_or(20)], |row| {
+            Ok(TopSearch { term: row.get(0)?, count: row.get(1)?, last_searched_at: row.get(2)? })
+        })
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    serde_json::to_string(&top).unwrap_or_else(|_| "[]".to_string())
+}
@@ -0,0 +1,551 @@
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::browser::{find_all_profiles, ProfileDir};
+use super::browser_redaction::{redact_title, redact_url};
+
+/// Magic bytes at the start of Firefox's LZ4-compressed `.jsonlz4` files.
+const MOZLZ4_MAGIC: &[u8] = b"mozLz40\0";
+
+/// Firefox session files, most current first: written while running, then on clean exit.
+const FIREFOX_SESSION_FILES: &[&str] = &[
+    "sessionstore-backups/recovery.jsonlz4",
+    "sessionstore.jsonlz4",
+    "sessionstore-backups/previous.jsonlz4",
+];
+
+/// Seconds between the Windows epoch (1601) used by Chromium and the Unix epoch.
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+// Command ids written by Chromium's session service (session_service_commands.cc).
+const CMD_SET_TAB_WINDOW: u8 = 0;
+const CMD_SET_TAB_INDEX_IN_WINDOW: u8 = 2;
+const CMD_NAVIGATION_PATH_PRUNED_FROM_BACK: u8 = 5;
+const CMD_UPDATE_TAB_NAVIGATION: u8 = 6;
+const CMD_SET_SELECTED_NAVIGATION_INDEX: u8 = 7;
+const CMD_SET_SELECTED_TAB_IN_INDEX: u8 = 8;
+const CMD_NAVIGATION_PATH_PRUNED_FROM_FRONT: u8 = 11;
+const CMD_SET_PINNED_STATE: u8 = 12;
+const CMD_TAB_CLOSED: u8 = 16;
+const CMD_WINDOW_CLOSED: u8 = 17;
+const CMD_SET_ACTIVE_WINDOW: u8 = 20;
+const CMD_LAST_ACTIVE_TIME: u8 = 21;
+const CMD_NAVIGATION_PATH_PRUNED: u8 = 24;
+
+#[derive(Serialize)]
+pub struct OpenTab {
+    pub(crate) url: String,
+    pub(crate) title: String,
+    pub(crate) pinned: bool,
+    pub(crate) selected: bool,
+    pub(crate) last_active: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct OpenWindow {
+    pub(crate) active: bool,
+    pub(crate) tabs: Vec<OpenTab>,
+}
+
+#[derive(Serialize)]
+pub struct ProfileTabs {
+    browser: String,
+    profile: String,
+    display_name: String,
+    source: Option<String>,
+    windows: Vec<OpenWindow>,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct TabState {
+    window_id: Option<i32>,
+    index_in_window: Option<i32>,
+    /// Navigation index -> (url, title).
+    navigations: BTreeMap<i32, (String, String)>,
+    selected_navigation: Option<i32>,
+    pinned: bool,
+    last_active: Option<i64>,
+}
+
+#[derive(Default)]
+struct WindowState {
+    selected_tab_index: Option<i32>,
+    closed: bool,
+}
+
+/// Reads fields from a command payload, following Chromium's `base::Pickle` layout.
+struct PayloadReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PayloadReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.bytes(4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn i64_at(&self, offset: usize) -> Option<i64> {
+        let b = self.data.get(offset..offset + 8)?;
+        Some(i64::from_le_bytes(b.try_into().ok()?))
+    }
+
+    fn u8_at(&self, offset: usize) -> Option<u8> {
+        self.data.get(offset).copied()
+    }
+
+    /// Pickle fields are padded to four bytes.
+    fn align(&mut self) {
+        self.pos = (self.pos + 3) & !3;
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = usize::try_from(self.i32()?).ok()?;
+        let bytes = self.bytes(len)?;
+        self.align();
+        Some(String::from_utf8_lossy(bytes).to_string())
+    }
+
+    fn string16(&mut self) -> Option<String> {
+        let len = usize::try_from(self.i32()?).ok()?;
+        let bytes = self.bytes(len.checked_mul(2)?)?;
+        self.align();
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        Some(String::from_utf16_lossy(&units))
+    }
+}
+
+fn chromium_time_to_local(micros: i64) -> Option<String> {
+    let unix_seconds = micros / 1_000_000 - WINDOWS_EPOCH_OFFSET;
+    // Older Chromium wrote TimeTicks here, which are not wall-clock times.
+    if !(946_684_800..4_102_444_800).contains(&unix_seconds) {
+        return None;
+    }
+    Local
+        .timestamp_opt(unix_seconds, 0)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// Removes `count` navigations starting at `index` and shifts later entries down.
+fn prune_navigations(tab: &mut TabState, index: i32, count: i32) {
+    tab.navigations = std::mem::take(&mut tab.navigations)
+        .into_iter()
+        .filter(|(i, _)| *i < index || *i >= index + count)
+        .map(|(i, nav)| if i >= index + count { (i - count, nav) } else { (i, nav) })
+        .collect();
+}
+
+/// Replays the commands of an SNSS session file into the windows and tabs that are open.
+pub(crate) fn parse_snss(data: &[u8]) -> Result<Vec<OpenWindow>, String> {
+    if data.len() < 8 || &data[..4] != b"SNSS" {
+        return Err("not an SNSS session file".to_string());
+    }
+    let version = i32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    if version != 1 && version != 3 {
+        return Err(format!("unsupported SNSS version {} (encrypted sessions are not readable)", version));
+    }
+
+    let mut tabs: HashMap<i32, TabState> = HashMap::new();
+    let mut windows: HashMap<i32, WindowState> = HashMap::new();
+    let mut active_window: Option<i32> = None;
+
+    let mut pos = 8;
+    while pos + 2 <= data.len() {
+        let size = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+        pos += 2;
+        if size == 0 || pos + size > data.len() {
+            break;
+        }
+        let id = data[pos];
+        let payload = &data[pos + 1..pos + size];
+        pos += size;
+
+        let mut reader = PayloadReader::new(payload);
+        match id {
+            CMD_SET_TAB_WINDOW => {
+                if let (Some(window_id), Some(tab_id)) = (reader.i32(), reader.i32()) {
+                    tabs.entry(tab_id).or_default().window_id = Some(window_id);
+                    windows.entry(window_id).or_default();
+                }
+            }
+            CMD_SET_TAB_INDEX_IN_WINDOW => {
+                if let (Some(tab_id), Some(index)) = (reader.i32(), reader.i32()) {
+                    tabs.entry(tab_id).or_default().index_in_window = Some(index);
+                }
+            }
+            CMD_UPDATE_TAB_NAVIGATION => {
+                // Pickle header (payload size), then tab id, navigation index, URL and title.
+                reader.i32();
+                if let (Some(tab_id), Some(index), Some(url), Some(title)) =
+                    (reader.i32(), reader.i32(), reader.string(), reader.string16())
+                {
+                    tabs.entry(tab_id).or_default().navigations.insert(index, (url, title));
+                }
+            }
+            CMD_SET_SELECTED_NAVIGATION_INDEX => {
+                if let (Some(tab_id), Some(index)) = (reader.i32(), reader.i32()) {
+                    tabs.entry(tab_id).or_default().selected_navigation = Some(index);
+                }
+            }
+            CMD_SET_SELECTED_TAB_IN_INDEX => {
+                if let (Some(window_id), Some(index)) = (reader.i32(), reader.i32()) {
+                    windows.entry(window_id).or_default().selected_tab_index = Some(index);
+                }
+            }
+            CMD_SET_PINNED_STATE => {
+                if let (Some(tab_id), Some(pinned)) = (reader.i32(), reader.u8_at(4)) {
+                    tabs.entry(tab_id).or_default().pinned = pinned != 0;
+                }
+            }
+            CMD_TAB_CLOSED => {
+                if let Some(tab_id) = reader.i32() {
+                    tabs.remove(&tab_id);
+                }
+            }
+            CMD_WINDOW_CLOSED => {
+                if let Some(window_id) = reader.i32() {
+                    windows.entry(window_id).or_default().closed = true;
+                }
+            }
+            CMD_SET_ACTIVE_WINDOW => {
+                active_window = reader.i32();
+            }
+            CMD_LAST_ACTIVE_TIME => {
+                // The int64 is 8-byte aligned, so it follows four bytes of padding.
+                if let (Some(tab_id), Some(time)) = (reader.i32(), reader.i64_at(8)) {
+                    tabs.entry(tab_id).or_default().last_active = Some(time);
+                }
+            }
+            CMD_NAVIGATION_PATH_PRUNED_FROM_BACK => {
+                if let (Some(tab_id), Some(index)) = (reader.i32(), reader.i32()) {
+                    if let Some(tab) = tabs.get_mut(&tab_id) {
+                        tab.navigations.retain(|i, _| *i < index);
+                    }
+                }
+            }
+            CMD_NAVIGATION_PATH_PRUNED_FROM_FRONT => {
+                if let (Some(tab_id), Some(count)) = (reader.i32(), reader.i32()) {
+                    if let Some(tab) = tabs.get_mut(&tab_id) {
+                        prune_navigations(tab, 0, count);
+                    }
+                }
+            }
+            CMD_NAVIGATION_PATH_PRUNED => {
+                if let (Some(tab_id), Some(index), Some(count)) = (reader.i32(), reader.i32(), reader.i32()) {
+                    if let Some(tab) = tabs.get_mut(&tab_id) {
+                        prune_navigations(tab, index, count);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut tabs_by_window: BTreeMap<i32, Vec<TabState>> = BTreeMap::new();
+    for tab in tabs.into_values() {
+        let Some(window_id) = tab.window_id else { continue };
+        if tab.navigations.is_empty() || windows.get(&window_id).map(|w| w.closed).unwrap_or(false) {
+            continue;
+        }
+        tabs_by_window.entry(window_id).or_default().push(tab);
+    }
+
+    let open_windows = tabs_by_window
+        .into_iter()
+        .map(|(window_id, mut window_tabs)| {
+            window_tabs.sort_by_key(|tab| tab.index_in_window.unwrap_or(i32::MAX));
+            let selected_tab_index = windows.get(&window_id).and_then(|w| w.selected_tab_index);
+
+            let tabs = window_tabs
+                .into_iter()
+                .map(|tab| {
+                    let (url, title) = tab
+                        .selected_navigation
+                        .and_then(|index| tab.navigations.get(&index).cloned())
+                        .or_else(|| tab.navigations.values().next_back().cloned())
+                        .unwrap_or_default();
+                    OpenTab {
+                        url,
+                        title,
+                        pinned: tab.pinned,
+                        selected: tab.index_in_window.is_some() && tab.index_in_window == selected_tab_index,
+                        last_active: tab.last_active.and_then(chromium_time_to_local),
+                    }
+                })
+                .collect();
+
+            OpenWindow { active: active_window == Some(window_id), tabs }
+        })
+        .collect();
+
+    Ok(open_windows)
+}
+
+/// Picks the newest `Sessions/Session_*` file, falling back to the pre-2021 `Current Session`.
+/// `Tabs_*` files belong to the tab-restore service and only hold recently closed tabs.
+fn latest_chromium_session_file(profile_path: &Path) -> Option<PathBuf> {
+    let newest = fs::read_dir(profile_path.join("Sessions"))
+        .ok()
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("Session_"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path);
+
+    newest.or_else(|| Some(profile_path.join("Current Session")).filter(|path| path.exists()))
+}
+
+fn chromium_open_tabs(dir: &ProfileDir) -> ProfileTabs {
+    let mut result = ProfileTabs {
+        browser: dir.browser.clone(),
+        profile: dir.profile.clone(),
+        display_name: dir.display_name.clone(),
+        source: None,
+        windows: Vec::new(),
+        error: None,
+    };
+
+    let Some(session_file) = latest_chromium_session_file(&dir.path) else {
+        result.error = Some("no session file found".to_string());
+        return result;
+    };
+    result.source = Some(session_file.to_string_lossy().to_string());
+
+    match fs::read(&session_file).map_err(|e| e.to_string()).and_then(|data| parse_snss(&data)) {
+        Ok(windows) => result.windows = windows,
+        Err(err) => result.error = Some(err),
+    }
+    result
+}
+
+/// Decodes a mozLz4 file: magic, little-endian decompressed size, then one LZ4 block.
+pub(crate) fn decode_mozlz4(data: &[u8]) -> Result<Vec<u8>, String> {
+    let header_len = MOZLZ4_MAGIC.len() + 4;
+    if data.len() < header_len || !data.starts_with(MOZLZ4_MAGIC) {
+        return Err("not a mozLz4 file".to_string());
+    }
+    let size_bytes = &data[MOZLZ4_MAGIC.len()..header_len];
+    let size = u32::from_le_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]) as usize;
+    lz4_flex::block::decompress(&data[header_len..], size).map_err(|e| e.to_string())
+}
+
+fn firefox_time_to_local(millis: i64) -> Option<String> {
+    Local
+        .timestamp_millis_opt(millis)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// Converts a Firefox session (`sessionstore.js` JSON) into open windows and tabs.
+/// Tab `index`, window `selected` and `selectedWindow` are all 1-based.
+pub(crate) fn parse_firefox_session(session: &Value) -> Vec<OpenWindow> {
+    let selected_window = session.get("selectedWindow").and_then(Value::as_i64).unwrap_or(1);
+
+    session
+        .get("windows")
+        .and_then(Value::as_array)
+        .map(|windows| {
+            windows
+                .iter()
+                .enumerate()
+                .map(|(window_index, window)| {
+                    let selected_tab = window.get("selected").and_then(Value::as_i64).unwrap_or(1);
+                    let tabs = window
+                        .get("tabs")
+                        .and_then(Value::as_array)
+                        .map(|tabs| {
+                            tabs.iter()
+                                .enumerate()
+                                .filter(|(_, tab)| !tab.get("hidden").and_then(Value::as_bool).unwrap_or(false))
+                                .filter_map(|(tab_index, tab)| {
+                                    let entries = tab.get("entries").and_then(Value::as_array)?;
+                                    let current = tab.get("index").and_then(Value::as_i64).unwrap_or(entries.len() as i64);
+                                    let entry = entries
+                                        .get((current - 1).max(0) as usize)
+                                        .or_else(|| entries.last())?;
+
+                                    Some(OpenTab {
+                                        url: entry.get("url").and_then(Value::as_str).unwrap_or("").to_string(),
+                                        title: entry.get("title").and_then(Value::as_str).unwrap_or("").to_string(),
+                                        pinned: tab.get("pinned").and_then(Value::as_bool).unwrap_or(false),
+                                        selected: tab_index as i64 + 1 == selected_tab,
+                                        last_active: tab.get("lastAccessed").and_then(Value::as_i64).and_then(firefox_time_to_local),
+                                    })
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+
+                    OpenWindow { active: window_index as i64 + 1 == selected_window, tabs }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn firefox_open_tabs(dir: &ProfileDir) -> ProfileTabs {
+    let mut result = ProfileTabs {
+        browser: dir.browser.clone(),
+        profile: dir.profile.clone(),
+        display_name: dir.display_name.clone(),
+        source: None,
+        windows: Vec::new(),
+        error: None,
+    };
+
+    let Some(session_file) = FIREFOX_SESSION_FILES.iter().map(|file| dir.path.join(file)).find(|path| path.exists()) else {
+        result.error = Some("no session file found".to_string());
+        return result;
+    };
+    result.source = Some(session_file.to_string_lossy().to_string());
+
+    let session = fs::read(&session_file)
+        .map_err(|e| e.to_string())
+        .and_then(|data| decode_mozlz4(&data))
+        .and_then(|json| serde_json::from_slice::<Value>(&json).map_err(|e| e.to_string()));
+    match session {
+        Ok(session) => result.windows = parse_firefox_session(&session),
+        Err(err) => result.error = Some(err),
+    }
+    result
+}
+
+/// Applies the same URL and title redaction as the history views.
+fn redact_tabs(profile_tabs: &mut ProfileTabs) {
+    for tab in profile_tabs.windows.iter_mut().flat_map(|window| window.tabs.iter_mut()) {
+        tab.url = redact_url(&tab.url);
+        tab.title = redact_title(&tab.title);
+    }
+}
+
+/// Lists the windows and tabs currently open in each browser profile.
+#[tauri::command]
+pub fn get_open_tabs() -> String {
+    let open_tabs: Vec<ProfileTabs> = find_all_profiles()
+        .iter()
+        .map(|dir| if dir.browser == "Firefox" { firefox_open_tabs(dir) } else { chromium_open_tabs(dir) })
+        .map(|mut profile_tabs| {
+            redact_tabs(&mut profile_tabs);
+            profile_tabs
+        })
+        .collect();
+
+    serde_json::to_string(&open_tabs).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sessions/", $name))
+        };
+    }
+
+    fn urls(window: &OpenWindow) -> Vec<&str> {
+        window.tabs.iter().map(|tab| tab.url.as_str()).collect()
+    }
+
+    /// 2023-11-14 22:13:20 UTC, the time stored in the fixtures.
+    fn fixture_time() -> Option<String> {
+        Local.timestamp_opt(1_700_000_000, 0).single().map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+    }
+
+    #[test]
+    fn replays_tabs_and_windows() {
+        // Window 1: tab 10 (two navigations, second selected), tab 11 (pinned, selected tab),
+        // tab 12 (closed). Window 2: tab 20, active. Window 3: tab 30, closed.
+        let windows = parse_snss(fixture!("Session_replay")).unwrap();
+        assert_eq!(windows.len(), 2);
+
+        let first = &windows[0];
+        assert!(!first.active);
+        assert_eq!(urls(first), ["https://b.example/", "https://pinned.example/"]);
+        assert_eq!(first.tabs[0].title, "B");
+        assert_eq!(first.tabs[0].last_active, fixture_time());
+        assert!(!first.tabs[0].pinned && !first.tabs[0].selected);
+        assert!(first.tabs[1].pinned && first.tabs[1].selected);
+        assert_eq!(first.tabs[1].last_active, None);
+
+        let second = &windows[1];
+        assert!(second.active);
+        assert_eq!(urls(second), ["https://c.example/"]);
+    }
+
+    #[test]
+    fn applies_navigation_pruning() {
+        // Each tab starts with navigations 0–3: tab 1 is pruned from the back at 2, tab 2 from
+        // the front by 2 (then selects 0), tab 3 loses 1–2 in the middle (then selects 1).
+        let windows = parse_snss(fixture!("Session_pruned")).unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(urls(&windows[0]), ["https://a1.example/", "https://b2.example/", "https://c3.example/"]);
+    }
+
+    #[test]
+    fn rejects_encrypted_versions() {
+        for data in [&fixture!("Session_v2_encrypted")[..], &fixture!("Session_v4_encrypted")[..]] {
+            let err = parse_snss(data).err().unwrap();
+            assert!(err.contains("encrypted"), "{}", err);
+        }
+        assert!(parse_snss(b"SNSX\x01\x00\x00\x00").is_err());
+        assert!(parse_snss(b"SNSS").is_err());
+    }
+
+    #[test]
+    fn stops_at_truncated_payloads() {
+        // Tab 1 is complete; tab 2's title runs past its payload; the command for tab 3 is cut off.
+        let windows = parse_snss(fixture!("Session_truncated")).unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(urls(&windows[0]), ["https://kept.example/"]);
+        assert_eq!(windows[0].tabs[0].title, "Kept");
+
+        let data = fixture!("Session_replay");
+        for len in 8..data.len() {
+            parse_snss(&data[..len]).unwrap();
+        }
+    }
+
+    #[test]
+    fn decodes_firefox_session() {
+        let json = decode_mozlz4(fixture!("recovery.jsonlz4")).unwrap();
+        let windows = parse_firefox_session(&serde_json::from_slice(&json).unwrap());
+        assert_eq!(windows.len(), 2);
+
+        // The hidden tab is skipped; `index` picks the second entry.
+        let first = &windows[0];
+        assert!(!first.active);
+        assert_eq!(urls(first), ["https://second.example/"]);
+        assert_eq!(first.tabs[0].title, "Second");
+        assert!(first.tabs[0].pinned && first.tabs[0].selected);
+        assert_eq!(first.tabs[0].last_active, fixture_time());
+
+        let second = &windows[1];
+        assert!(second.active);
+        assert_eq!(urls(second), ["https://other.example/", "https://current.example/"]);
+        assert!(!second.tabs[0].selected && second.tabs[1].selected);
+    }
+
+    #[test]
+    fn rejects_malformed_mozlz4() {
+        let data = fixture!("recovery.jsonlz4");
+        assert!(decode_mozlz4(b"mozLz40\0").is_err());
+        assert!(decode_mozlz4(&data[1..]).is_err());
+        assert!(decode_mozlz4(&data[..data.len() - 10]).is_err());
+    }
+}
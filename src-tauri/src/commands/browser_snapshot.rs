@@ -0,0 +1,145 @@
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Prefix of every snapshot directory created under the system temp dir.
+const SNAPSHOT_PREFIX: &str = "ems_snapshot_";
+
+/// Snapshot directories older than this are left over from a crash and get removed.
+const STALE_SNAPSHOT_AGE: Duration = Duration::from_secs(3600);
+
+/// SQLite side files that hold changes not yet checkpointed into the main database.
+const SIDE_FILE_SUFFIXES: &[&str] = &["-wal", "-journal"];
+
+static SNAPSHOT_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A private, read-only copy of a browser database that is deleted on drop.
+pub struct DbSnapshot {
+    conn: Option<Connection>,
+    dir: PathBuf,
+}
+
+impl DbSnapshot {
+    pub fn connection(&self) -> &Connection {
+        self.conn.as_ref().expect("snapshot connection is only taken on drop")
+    }
+}
+
+impl Drop for DbSnapshot {
+    fn drop(&mut self) {
+        // Close the connection first; Windows refuses to delete open files.
+        drop(self.conn.take());
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            eprintln!("Failed to remove snapshot {}: {}", self.dir.display(), err);
+        }
+    }
+}
+
+/// Keeps ASCII letters, digits, `-` and `_` so display names are safe in file names.
+fn sanitize_label(label: &str) -> String {
+    let sanitized: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .take(40)
+        .collect();
+    if sanitized.is_empty() { "db".to_string() } else { sanitized }
+}
+
+fn unique_snapshot_dir(label: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let counter = SNAPSHOT_COUNTER.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!(
+        "{}{}_{}_{}_{}",
+        SNAPSHOT_PREFIX,
+        sanitize_label(label),
+        std::process::id(),
+        nanos,
+        counter
+    ))
+}
+
+fn remove_stale_snapshots() {
+    let Ok(entries) = fs::read_dir(env::temp_dir()) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let is_snapshot = entry.file_name().to_string_lossy().starts_with(SNAPSHOT_PREFIX);
+        let is_stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age > STALE_SNAPSHOT_AGE)
+            .unwrap_or(false);
+        if is_snapshot && is_stale {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+fn side_file(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Copies the database together with its WAL/journal so uncheckpointed visits are kept.
+fn copy_with_side_files(source: &Path, dest: &Path) -> Result<(), String> {
+    fs::copy(source, dest).map_err(|e| e.to_string())?;
+    for suffix in SIDE_FILE_SUFFIXES {
+        let source_side = side_file(source, suffix);
+        if source_side.exists() {
+            fs::copy(&source_side, side_file(dest, suffix)).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Falls back to SQLite's online backup API when the file cannot be copied directly.
+fn backup_database(source: &Path, dest: &Path) -> Result<(), String> {
+    let source_conn = Connection::open_with_flags(
+        source,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| e.to_string())?;
+    source_conn
+        .backup(DatabaseName::Main, dest, None)
+        .map_err(|e| e.to_string())
+}
+
+/// Takes a consistent snapshot of a (possibly locked) SQLite database.
+///
+/// The copy lives in a uniquely named temp directory and is removed when the
+/// returned `DbSnapshot` is dropped, whether or not reading it succeeded.
+pub fn snapshot_database(source: &Path, label: &str) -> Result<DbSnapshot, String> {
+    remove_stale_snapshots();
+
+    let dir = unique_snapshot_dir(label);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    // From here on the guard owns the directory, so every early return cleans it up.
+    let mut snapshot = DbSnapshot { conn: None, dir };
+
+    let file_name = source.file_name().unwrap_or_else(|| "snapshot.db".as_ref());
+    let dest = snapshot.dir.join(file_name);
+
+    if let Err(copy_err) = copy_with_side_files(source, &dest) {
+        for suffix in SIDE_FILE_SUFFIXES {
+            let _ = fs::remove_file(side_file(&dest, suffix));
+        }
+        let _ = fs::remove_file(&dest);
+        backup_database(source, &dest)
+            .map_err(|backup_err| format!("copy failed ({}), backup failed ({})", copy_err, backup_err))?;
+    }
+
+    // Opening read-write lets SQLite replay the copied WAL into the snapshot.
+    let conn = Connection::open(&dest).map_err(|e| e.to_string())?;
+    snapshot.conn = Some(conn);
+    Ok(snapshot)
+}
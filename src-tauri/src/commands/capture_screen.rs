@@ -1,62 +1,746 @@
 use screenshots::Screen;
-use std::fs::{create_dir_all, File};
+use std::fs::{self, create_dir_all, write, OpenOptions};
+use std::io::{Cursor, ErrorKind, Write};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use image::{DynamicImage, ImageOutputFormat, imageops::FilterType};
+use image::{imageops, DynamicImage, ImageOutputFormat, RgbaImage, imageops::FilterType};
+use image::codecs::avif::AvifEncoder;
+use image::codecs::webp::{WebPEncoder, WebPQuality};
 use chrono::prelude::*; // For handling IST time
-use tokio::time;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{task, time};
+use tokio_cron_scheduler::{Job, JobScheduler};
 use tauri::command;
+use crate::utils::file::load_json_config;
+use aes_gcm::Aes256Gcm;
+use super::afk_tracker::{is_afk, is_session_locked};
+use super::private_browsing::mask_private_title;
+use super::screenshot_activity::{measure_activity, ScreenActivity};
+use super::screenshot_crypto::{cached_cipher, decrypt, encrypt, encrypt_file, encrypted_path, is_encrypted, EncryptionConfig};
+use super::screenshot_dedup::{
+    find_duplicate, hash_to_hex, perceptual_hash, pixel_hash, remember_capture, DuplicateMatch, StoredCapture,
+};
+use super::screenshot_evidence::{signature_path, EvidenceConfig};
+use super::screenshot_index::{record_activity_sample, record_screenshot, update_screenshot_path};
+use super::screenshot_masking::{mask_display, MaskingConfig, Region};
+use super::screenshot_schedule::ScheduleConfig;
+use super::screenshot_storage::{enforce_quota, StorageConfig};
+use super::screenshot_timelapse::{generate_timelapse_for, TimelapseConfig};
+use super::screenshot_triggers::{start_trigger_listener, CaptureTrigger, TriggerConfig};
+use super::visible_apps::{get_foreground_window, get_foreground_window_geometry, get_window_geometries};
 
 /// Directory to save screenshots
-const SCREENSHOT_DIR: &str = "D:\\Meltx\\emsScreenshots";
+pub(crate) const SCREENSHOT_DIR: &str = "D:\\Meltx\\emsScreenshots";
+
+const SCREENSHOT_CONFIG_FILE: &str = "screenshot_config.json";
+
+/// Thumbnails are JPEG regardless of the screenshot format, so the gallery can always show them.
+const THUMBNAIL_QUALITY: u8 = 70;
+
+/// AVIF encoder speed (1 = slowest/smallest, 10 = fastest); captures favour speed.
+const AVIF_SPEED: u8 = 8;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureLayout {
+    /// One image of the whole desktop, with displays placed at their screen positions.
+    Stitched,
+    /// One image per display.
+    PerDisplay,
+    /// Only the foreground window (plus `active_window_margin`), or the stitched desktop
+    /// when the window's bounds are unavailable.
+    ActiveWindow,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    Jpeg,
+    Png,
+    Webp,
+    Avif,
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Avif => "avif",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ScreenshotConfig {
+    layout: CaptureLayout,
+    /// Longest side, in pixels, of each display in the saved image. Aspect ratio is kept
+    /// and captures are never scaled up.
+    max_dimension: u32,
+    format: OutputFormat,
+    /// Encoder quality from 1 to 100; ignored for PNG, which is lossless.
+    quality: u8,
+    /// Skip scheduled captures while the user is AFK or the session is locked. While AFK but
+    /// unlocked the screen is still sampled, unsaved, so activity keeps being scored.
+    skip_when_idle: bool,
+    /// Store near-identical consecutive captures once and reference the earlier file.
+    deduplicate: bool,
+    /// What counts as near-identical; `exact` only skips pixel-identical captures.
+    duplicate_match: DuplicateMatch,
+    /// Maximum number of differing perceptual-hash bits (out of 256) for two captures to count as
+    /// identical. Kept low because a few lines of new text only flip a few bits.
+    duplicate_threshold: u32,
+    /// Windows that are blurred or blacked out before anything is saved.
+    masking: MaskingConfig,
+    /// Encryption of screenshot files at rest.
+    pub(crate) encryption: EncryptionConfig,
+    /// When scheduled captures run.
+    pub(crate) schedule: ScheduleConfig,
+    /// Daily timelapse generation.
+    pub(crate) timelapse: TimelapseConfig,
+    /// Longest side, in pixels, of the gallery thumbnail saved with each screenshot.
+    pub(crate) thumbnail_size: u32,
+    /// Quota and low-disk limits for `SCREENSHOT_DIR`.
+    pub(crate) storage: StorageConfig,
+    /// Events that capture outside the schedule.
+    pub(crate) triggers: TriggerConfig,
+    /// Pixels of desktop kept around the window in the `active_window` layout.
+    active_window_margin: u32,
+    /// Watermarking and signing for use as evidence.
+    pub(crate) evidence: EvidenceConfig,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            layout: CaptureLayout::Stitched,
+            max_dimension: 1280,
+            format: OutputFormat::Jpeg,
+            quality: 70,
+            skip_when_idle: true,
+            deduplicate: true,
+            duplicate_match: DuplicateMatch::Perceptual,
+            duplicate_threshold: 2,
+            masking: MaskingConfig::default(),
+            encryption: EncryptionConfig::default(),
+            schedule: ScheduleConfig::default(),
+            timelapse: TimelapseConfig::default(),
+            thumbnail_size: 240,
+            storage: StorageConfig::default(),
+            triggers: TriggerConfig::default(),
+            active_window_margin: 0,
+            evidence: EvidenceConfig::default(),
+        }
+    }
+}
+
+pub(crate) fn load_screenshot_config() -> ScreenshotConfig {
+    load_json_config(SCREENSHOT_CONFIG_FILE)
+}
+
+/// Position and resolution of a display, in desktop coordinates.
+#[derive(Serialize, Clone)]
+pub struct DisplayMeta {
+    id: u32,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    scale_factor: f32,
+    is_primary: bool,
+}
+
+#[derive(Serialize)]
+pub struct SavedScreenshot {
+    /// Row id in the `screenshots` table; `None` if indexing failed.
+    pub(crate) id: Option<i64>,
+    pub(crate) path: String,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) format: &'static str,
+    /// Bytes written for this capture; 0 when it references an earlier file.
+    pub(crate) bytes: u64,
+    /// SHA-256 of the encoded image (before encryption), hex encoded.
+    pub(crate) content_hash: String,
+    pub(crate) perceptual_hash: String,
+    /// Gallery thumbnail; `None` if it could not be created.
+    pub(crate) thumbnail_path: Option<String>,
+    /// Id of the earlier screenshot whose file this capture reuses.
+    pub(crate) duplicate_of: Option<i64>,
+    /// Change since the previous capture of the same displays; `None` for the first one.
+    pub(crate) activity: Option<ScreenActivity>,
+    pub(crate) displays: Vec<DisplayMeta>,
+}
+
+/// What the user was doing when a capture was taken.
+pub struct CaptureContext {
+    pub captured_at: DateTime<Utc>,
+    pub window_title: Option<String>,
+    pub process_name: Option<String>,
+    pub afk: bool,
+    pub trigger: CaptureTrigger,
+}
+
+impl CaptureContext {
+    fn current(trigger: CaptureTrigger) -> Self {
+        let window = get_foreground_window();
+        let window_title = window.as_ref().map(|window| mask_private_title(&window.process_name, window.title.clone()));
+
+        Self {
+            captured_at: Utc::now(),
+            window_title,
+            process_name: window.map(|window| window.process_name),
+            afk: is_afk(),
+            trigger,
+        }
+    }
+}
+
+impl DisplayMeta {
+    fn bounds(&self) -> Region {
+        Region { x: self.x, y: self.y, width: self.width, height: self.height }
+    }
+}
+
+/// One display and its full-resolution capture.
+type DisplayCapture = (DisplayMeta, RgbaImage);
+/// An image to save, the displays it covers, and the file name suffix.
+type CaptureFrame = (Vec<DisplayMeta>, DynamicImage, String);
+
+fn display_meta(screen: &Screen) -> DisplayMeta {
+    let info = &screen.display_info;
+    DisplayMeta {
+        id: info.id,
+        x: info.x,
+        y: info.y,
+        width: info.width,
+        height: info.height,
+        scale_factor: info.scale_factor,
+        is_primary: info.is_primary,
+    }
+}
+
+/// Places every capture at its display position on one canvas covering the whole desktop.
+/// Captures are in physical pixels, so each is scaled to its display's desktop size first.
+fn stitch_displays(captures: &[DisplayCapture]) -> RgbaImage {
+    let min_x = captures.iter().map(|(d, _)| d.x).min().unwrap_or(0);
+    let min_y = captures.iter().map(|(d, _)| d.y).min().unwrap_or(0);
+    let max_x = captures.iter().map(|(d, _)| d.x + d.width as i32).max().unwrap_or(0);
+    let max_y = captures.iter().map(|(d, _)| d.y + d.height as i32).max().unwrap_or(0);
+
+    let mut canvas = RgbaImage::new((max_x - min_x).max(1) as u32, (max_y - min_y).max(1) as u32);
+    for (display, image) in captures {
+        let scaled;
+        let image = if image.dimensions() == (display.width, display.height) {
+            image
+        } else {
+            scaled = imageops::resize(image, display.width, display.height, FilterType::Triangle);
+            &scaled
+        };
+        imageops::overlay(&mut canvas, image, (display.x - min_x) as i64, (display.y - min_y) as i64);
+    }
+    canvas
+}
+
+/// Stitches all displays into one frame, scaled to `max_dimension` per display.
+fn stitched_frame(captures: Vec<DisplayCapture>, max_dimension: u32) -> CaptureFrame {
+    let stitched = DynamicImage::ImageRgba8(stitch_displays(&captures));
+    // The canvas is in desktop coordinates, so the limit applies to the largest display
+    let largest_side = captures.iter().map(|(d, _)| d.width.max(d.height)).max().unwrap_or(0);
+    let resized_img = fit_to_max_dimension(stitched, largest_side, max_dimension);
+    (captures.into_iter().map(|(display, _)| display).collect(), resized_img, String::new())
+}
+
+/// Crops the displays to the foreground window grown by `margin` on each side. A window
+/// spanning several displays is cut from their stitched image. Returns the captures
+/// unchanged when the window's bounds are unavailable or off-screen.
+fn window_frame(
+    captures: Vec<DisplayCapture>,
+    margin: u32,
+    max_dimension: u32,
+) -> Result<CaptureFrame, Vec<DisplayCapture>> {
+    let Some(window) = get_foreground_window_geometry() else {
+        return Err(captures);
+    };
+    let region = Region {
+        x: window.x - margin as i32,
+        y: window.y - margin as i32,
+        width: window.width + margin * 2,
+        height: window.height + margin * 2,
+    };
+    let (covered, rest): (Vec<_>, Vec<_>) =
+        captures.into_iter().partition(|(display, _)| region.intersect(&display.bounds()).is_some());
+    if covered.is_empty() {
+        return Err(rest);
+    }
+
+    let min_x = covered.iter().map(|(d, _)| d.x).min().unwrap_or(0);
+    let min_y = covered.iter().map(|(d, _)| d.y).min().unwrap_or(0);
+    let canvas = stitch_displays(&covered);
+    let canvas_bounds = Region { x: min_x, y: min_y, width: canvas.width(), height: canvas.height() };
+    // The displays may not fill the canvas, but the window does intersect it
+    let crop = region.intersect(&canvas_bounds).unwrap_or(canvas_bounds);
+
+    let cropped = imageops::crop_imm(&canvas, (crop.x - min_x) as u32, (crop.y - min_y) as u32, crop.width, crop.height).to_image();
+    let largest_side = cropped.width().max(cropped.height());
+    let resized_img = fit_to_max_dimension(DynamicImage::ImageRgba8(cropped), largest_side, max_dimension);
+    Ok((covered.into_iter().map(|(display, _)| display).collect(), resized_img, "-window".to_string()))
+}
+
+/// Scales the image so that a display of `largest_side` pixels is at most `max_dimension`
+/// on its longest side, keeping the aspect ratio.
+fn fit_to_max_dimension(image: DynamicImage, largest_side: u32, max_dimension: u32) -> DynamicImage {
+    if max_dimension == 0 || largest_side <= max_dimension {
+        return image;
+    }
+    let scale = max_dimension as f64 / largest_side as f64;
+    let width = ((image.width() as f64 * scale).round() as u32).max(1);
+    let height = ((image.height() as f64 * scale).round() as u32).max(1);
+    image.resize_exact(width, height, FilterType::Lanczos3)
+}
+
+/// Encodes the image in the configured format. Screenshots carry no transparency,
+/// so the alpha channel is dropped first.
+fn encode_image(image: &DynamicImage, format: OutputFormat, quality: u8) -> Result<Vec<u8>, String> {
+    let image = DynamicImage::ImageRgb8(image.to_rgb8());
+    let quality = quality.clamp(1, 100);
+    let mut bytes = Vec::new();
+
+    match format {
+        OutputFormat::Jpeg => image.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Jpeg(quality)),
+        OutputFormat::Png => image.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png),
+        OutputFormat::Webp => {
+            // Lossy WebP is deprecated in `image` but is the only way to get a quality setting.
+            #[allow(deprecated)]
+            let encoder = WebPEncoder::new_with_quality(&mut bytes, WebPQuality::lossy(quality));
+            image.write_with_encoder(encoder)
+        }
+        OutputFormat::Avif => image.write_with_encoder(AvifEncoder::new_with_speed_quality(&mut bytes, AVIF_SPEED, quality)),
+    }
+    .map_err(|e| e.to_string())?;
+
+    Ok(bytes)
+}
+
+/// `stem.ext` for the first attempt, then `stem-1.ext`, `stem-2.ext`, ...
+fn numbered_path(filepath: &Path, attempt: u32) -> PathBuf {
+    if attempt == 0 {
+        return filepath.to_path_buf();
+    }
+    let stem = filepath.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match filepath.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, attempt, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, attempt),
+    };
+    filepath.with_file_name(name)
+}
+
+/// Compresses the image and writes it to `filepath`, encrypted when a cipher is given.
+/// An existing file is never replaced: when a capture in the same millisecond already took the
+/// name, a counter is appended, so its image and signature stay valid.
+/// Returns the path written, the file size and the content hash.
+fn save_image(
+    image: &DynamicImage,
+    filepath: &Path,
+    config: &ScreenshotConfig,
+    cipher: Option<&Aes256Gcm>,
+) -> Result<(PathBuf, u64, String), String> {
+    let bytes = encode_image(image, config.format, config.quality)?;
+    let content_hash = format!("{:x}", Sha256::digest(&bytes));
+
+    let bytes = match cipher {
+        Some(cipher) => encrypt(cipher, &bytes)?,
+        None => bytes,
+    };
+
+    let mut attempt = 0;
+    loop {
+        let candidate = numbered_path(filepath, attempt);
+        let candidate = if cipher.is_some() { encrypted_path(&candidate) } else { candidate };
+        match OpenOptions::new().write(true).create_new(true).open(&candidate) {
+            Ok(mut file) => {
+                file.write_all(&bytes).map_err(|e| e.to_string())?;
+                return Ok((candidate, bytes.len() as u64, content_hash));
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Scales the image down to `size` on its longest side and encodes it as a JPEG thumbnail.
+pub(crate) fn make_thumbnail(image: &DynamicImage, size: u32) -> Result<Vec<u8>, String> {
+    encode_image(&image.thumbnail(size.max(1), size.max(1)), OutputFormat::Jpeg, THUMBNAIL_QUALITY)
+}
+
+/// Writes the thumbnail of the saved screenshot at `filepath` to the `thumbnails` folder, encrypted when a cipher is given.
+fn save_thumbnail(
+    image: &DynamicImage,
+    filepath: &Path,
+    size: u32,
+    cipher: Option<&Aes256Gcm>,
+) -> Result<PathBuf, String> {
+    let dir = Path::new(SCREENSHOT_DIR).join("thumbnails");
+    create_dir_all(&dir).map_err(|e| e.to_string())?;
+    // The screenshot may already carry `.enc`, so cut the name at its first extension
+    let name = filepath.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = name.split('.').next().unwrap_or_default();
+    let thumbnail_path = dir.join(format!("{}.jpg", stem));
+
+    let bytes = make_thumbnail(image, size)?;
+    let (thumbnail_path, bytes) = match cipher {
+        Some(cipher) => (encrypted_path(&thumbnail_path), encrypt(cipher, &bytes)?),
+        None => (thumbnail_path, bytes),
+    };
+    write(&thumbnail_path, &bytes).map_err(|e| e.to_string())?;
+    Ok(thumbnail_path)
+}
+
+/// Reads a screenshot file, decrypting it if it was saved encrypted.
+pub(crate) fn read_screenshot_file(path: &Path) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+    let cipher = match cached_cipher() {
+        Some(cipher) => cipher,
+        None => load_screenshot_config().encryption.cipher()?,
+    };
+    decrypt(&cipher, &data)
+}
+
+/// Encrypts screenshots, thumbnails and timelapses saved before encryption was enabled and
+/// points their index rows at the encrypted files. Returns the number of files encrypted.
+pub fn encrypt_existing_screenshots() -> Result<usize, String> {
+    let config = load_screenshot_config();
+    if !config.encryption.enabled || !Path::new(SCREENSHOT_DIR).exists() {
+        return Ok(0);
+    }
+    let cipher = config.encryption.cipher()?;
+
+    let screenshot_dir = Path::new(SCREENSHOT_DIR);
+    let plain_files: Vec<PathBuf> = [screenshot_dir.to_path_buf(), screenshot_dir.join("thumbnails"), screenshot_dir.join("timelapse")]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ["jpg", "png", "webp", "avif", "gif", "avi"].contains(&ext.to_ascii_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    let mut encrypted = 0;
+    for path in plain_files {
+
+        match encrypt_file(&cipher, &path) {
+            Ok(target) => {
+                encrypted += 1;
+                // Signatures cover the plaintext hash, so they only need to follow the file
+                let signature = signature_path(&path);
+                if signature.exists() {
+                    if let Err(e) = fs::rename(&signature, signature_path(&target)) {
+                        eprintln!("Failed to move signature of {}: {}", path.display(), e);
+                    }
+                }
+                if let Err(e) = update_screenshot_path(&path.to_string_lossy(), &target.to_string_lossy()) {
+                    eprintln!("Failed to update index for {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => eprintln!("Failed to encrypt {}: {}", path.display(), e),
+        }
+    }
+    Ok(encrypted)
+}
+
+/// Captures every display, masks sensitive windows and arranges the result per `layout`.
+/// Each frame becomes one output file.
+fn capture_frames(config: &ScreenshotConfig) -> Result<Vec<CaptureFrame>, String> {
+    // Capture every display; a display that fails is skipped rather than failing the whole capture
+    let screens = Screen::all().map_err(|e| e.to_string())?;
+    let mut captures: Vec<DisplayCapture> = screens
+        .iter()
+        .filter_map(|screen| match screen.capture() {
+            Ok(image) => Some((display_meta(screen), image)),
+            Err(e) => {
+                eprintln!("Failed to capture display {}: {}", screen.display_info.id, e);
+                None
+            }
+        })
+        .collect();
+    if captures.is_empty() {
+        return Err("No screen found".to_string());
+    }
+
+    // Mask sensitive windows on the full-resolution captures, before any resizing or saving
+    if config.masking.enabled {
+        let regions = config.masking.sensitive_regions(&get_window_geometries());
+        if !regions.is_empty() {
+            for (display, image) in captures.iter_mut() {
+                mask_display(image, display.bounds(), &regions, config.masking.style);
+            }
+        }
+    }
+
+    // Each frame is one output file: the displays it covers, the image, and the file name suffix
+    let frames: Vec<CaptureFrame> = match config.layout {
+        CaptureLayout::Stitched => vec![stitched_frame(captures, config.max_dimension)],
+        CaptureLayout::PerDisplay => captures
+            .into_iter()
+            .map(|(display, image)| {
+                let largest_side = image.width().max(image.height());
+                let resized_img = fit_to_max_dimension(DynamicImage::ImageRgba8(image), largest_side, config.max_dimension);
+                let suffix = format!("-display{}", display.id);
+                (vec![display], resized_img, suffix)
+            })
+            .collect(),
+        CaptureLayout::ActiveWindow => match window_frame(captures, config.active_window_margin, config.max_dimension) {
+            Ok(frame) => vec![frame],
+            Err(captures) => {
+                eprintln!("Foreground window bounds unavailable; capturing the full desktop");
+                vec![stitched_frame(captures, config.max_dimension)]
+            }
+        },
+    };
+    Ok(frames)
+}
+
+/// Identifies the displays a frame covers, for comparing it with earlier frames of the same displays.
+fn displays_key(displays: &[DisplayMeta]) -> String {
+    displays.iter().map(|d| d.id.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// Captures every display, compresses the images, and saves them either stitched into
+/// one screenshot or as one screenshot per display (`layout` in `screenshot_config.json`).
+/// Each file is indexed in the `screenshots` table together with the foreground window, AFK state
+/// and the trigger. Returns the saved files with their size and the displays each one covers, as JSON.
+pub async fn capture_screens(trigger: CaptureTrigger) -> Result<String, String> {
+    let config = load_screenshot_config();
+    if let Some(free) = config.storage.low_disk_space() {
+        return Err(format!("Screenshot capture paused: only {} MB free on the screenshot disk", free / (1024 * 1024)));
+    }
+    let context = CaptureContext::current(trigger);
+    // Without a key nothing is saved, rather than writing plain files
+    let cipher = if config.encryption.enabled { Some(config.encryption.cipher()?) } else { None };
 
-/// Captures the current screen, compresses it, and saves the screenshot.
-#[command]
-pub async fn get_capture_screen() -> Result<String, String> {
     // Ensure the directory exists
     let screenshot_path = Path::new(SCREENSHOT_DIR);
     if !screenshot_path.exists() {
         create_dir_all(screenshot_path).map_err(|e| e.to_string())?;
     }
 
-    // Get all screens and select the primary one
-    let screens = Screen::all().map_err(|e| e.to_string())?;
-    let screen = screens.get(0).ok_or("No screen found")?;
-    
-    // Capture the screen image
-    let image = screen.capture().map_err(|e| e.to_string())?;
-    let img = DynamicImage::ImageRgba8(image.into());
+    // Get current time in IST
+    let now_ist = context.captured_at.with_timezone(&FixedOffset::east_opt(5 * 3600 + 1800).unwrap());
+    let formatted_time = now_ist.format("%Y-%m-%d_%H-%M-%S-%3f").to_string();
 
-    // Resize the image to reduce size (scale down to 720x480)
-    let resized_img = img.resize_exact(720, 480, FilterType::Lanczos3);
+    let mut saved = Vec::new();
+    for (displays, image, suffix) in capture_frames(&config)? {
+        let displays_key = displays_key(&displays);
+        let hash = perceptual_hash(&image);
+        let pixels = pixel_hash(&image);
+        let activity = measure_activity(&displays_key, context.captured_at, &image);
+        let duplicate = if config.deduplicate {
+            find_duplicate(&displays_key, &hash, &pixels, config.duplicate_match, config.duplicate_threshold)
+        } else {
+            None
+        };
 
-    // Get current time in IST
-    let now_utc = Utc::now();
-    let now_ist = now_utc.with_timezone(&FixedOffset::east_opt(5 * 3600 + 1800).unwrap());
-    let formatted_time = now_ist.format("%Y-%m-%d_%H-%M-%S").to_string();
+        let mut screenshot = match duplicate {
+            // Near-identical to the last stored capture: reference its file instead of writing a new one
+            Some(previous) => SavedScreenshot {
+                id: None,
+                path: previous.path,
+                width: image.width(),
+                height: image.height(),
+                format: previous.format,
+                bytes: 0,
+                content_hash: previous.content_hash,
+                perceptual_hash: hash_to_hex(&hash),
+                thumbnail_path: previous.thumbnail_path,
+                duplicate_of: Some(previous.id),
+                activity,
+                displays,
+            },
+            None => {
+                let filename = format!("screenshot-{}{}.{}", formatted_time, suffix, config.format.extension());
+                let filepath: PathBuf = screenshot_path.join(&filename);
+                let watermarked;
+                let image = if config.evidence.watermark {
+                    watermarked = config.evidence.watermark(&image, context.captured_at);
+                    &watermarked
+                } else {
+                    &image
+                };
+                let (filepath, bytes, content_hash) = save_image(image, &filepath, &config, cipher.as_ref())?;
+                // Only after the image is saved, so a failed save leaves no orphaned thumbnail
+                let thumbnail_path = save_thumbnail(image, &filepath, config.thumbnail_size, cipher.as_ref())
+                    .map_err(|e| eprintln!("Failed to save thumbnail for {}: {}", filepath.display(), e))
+                    .ok();
+                if config.evidence.sign {
+                    if let Err(e) = config.evidence.sign_screenshot(&filepath, &content_hash, context.captured_at) {
+                        eprintln!("Failed to sign {}: {}", filepath.display(), e);
+                    }
+                }
+                SavedScreenshot {
+                    id: None,
+                    path: filepath.to_string_lossy().to_string(),
+                    width: image.width(),
+                    height: image.height(),
+                    format: config.format.extension(),
+                    bytes,
+                    content_hash,
+                    perceptual_hash: hash_to_hex(&hash),
+                    thumbnail_path: thumbnail_path.map(|path| path.to_string_lossy().to_string()),
+                    duplicate_of: None,
+                    activity,
+                    displays,
+                }
+            }
+        };
+
+        match record_screenshot(&screenshot, &context) {
+            Ok(id) => {
+                screenshot.id = Some(id);
+                if screenshot.duplicate_of.is_none() {
+                    remember_capture(displays_key, StoredCapture {
+                        id,
+                        path: screenshot.path.clone(),
+                        format: screenshot.format,
+                        content_hash: screenshot.content_hash.clone(),
+                        thumbnail_path: screenshot.thumbnail_path.clone(),
+                        hash,
+                        pixels,
+                    });
+                }
+            }
+            Err(e) => eprintln!("Failed to index screenshot {}: {}", screenshot.path, e),
+        }
+        saved.push(screenshot);
+    }
+
+    if let Err(e) = enforce_quota(&config.storage) {
+        eprintln!("Failed to enforce screenshot quota: {}", e);
+    }
+
+    serde_json::to_string(&saved).map_err(|e| e.to_string())
+}
+
+/// Scores the screen against the previous capture without saving anything, and records the
+/// result for `get_screen_activity`. Used instead of a capture while the user is away.
+/// Returns the number of frames scored.
+fn sample_idle_activity(config: &ScreenshotConfig) -> Result<usize, String> {
+    let captured_at = Utc::now();
+    let mut scored = 0;
+    for (displays, image, _) in capture_frames(config)? {
+        if let Some(activity) = measure_activity(&displays_key(&displays), captured_at, &image) {
+            record_activity_sample(captured_at, &displays, &activity)?;
+            scored += 1;
+        }
+    }
+    Ok(scored)
+}
+
+/// Captures on request from the UI; see [`capture_screens`].
+#[command]
+pub async fn get_capture_screen() -> Result<String, String> {
+    capture_screens(CaptureTrigger::Manual).await
+}
 
-    // Generate filename with IST timestamp
-    let filename = format!("screenshot-{}.jpg", formatted_time);
-    let filepath: PathBuf = screenshot_path.join(&filename);
+/// One scheduled run of the cron `expression`: waits for the configured jitter, then captures
+/// unless outside working hours, on a day off, or while the user is away or the workstation is locked.
+async fn run_scheduled_capture(expression: String) {
+    let config = load_screenshot_config();
+    time::sleep(config.schedule.jitter(&expression)).await;
 
-    // Compress and save as JPEG (Quality: 70%)
-    let mut output_file = File::create(&filepath).map_err(|e| e.to_string())?;
-    resized_img.write_to(&mut output_file, ImageOutputFormat::Jpeg(70)) // Adjust quality 40-50KB
-        .map_err(|e| e.to_string())?;
+    if !config.schedule.allows(Local::now()) {
+        return;
+    }
+    if config.skip_when_idle && is_session_locked() {
+        println!("Skipping screenshot: the session is locked");
+        return;
+    }
+    if config.skip_when_idle && is_afk() {
+        // Nothing is saved while the user is away, but change without input (e.g. a video
+        // playing) is still measured
+        match sample_idle_activity(&config) {
+            Ok(_) => println!("Skipping screenshot: user is away; screen activity sampled"),
+            Err(e) => eprintln!("Skipping screenshot: user is away; failed to sample screen activity: {}", e),
+        }
+        return;
+    }
 
-    Ok(filepath.to_string_lossy().to_string())
+    match capture_screens(CaptureTrigger::Schedule).await {
+        Ok(saved) => println!("Compressed screenshots saved: {}", saved),
+        Err(e) => eprintln!("Failed to capture screenshot: {}", e),
+    }
 }
 
-/// Starts a background scheduler that captures compressed screenshots every 10 minutes.
+/// Encrypts any plain screenshots left from before encryption was enabled, then starts
+/// a background scheduler that captures compressed screenshots on the cron schedules in
+/// `screenshot_config.json` (every 10 minutes by default), plus the end-of-day timelapse.
 pub async fn start_screenshot_scheduler() {
     tokio::spawn(async {
-        let mut interval = time::interval(Duration::from_secs(600)); // 10 minutes
-        loop {
-            interval.tick().await;
-            match get_capture_screen().await {
-                Ok(filepath) => println!("Compressed screenshot saved at: {}", filepath),
-                Err(e) => eprintln!("Failed to capture screenshot: {}", e),
+        start_trigger_listener();
+
+        match encrypt_existing_screenshots() {
+            Ok(0) => {}
+            Ok(count) => println!("Encrypted {} existing screenshots", count),
+            Err(e) => eprintln!("Failed to encrypt existing screenshots: {}", e),
+        }
+
+        let config = load_screenshot_config();
+        let scheduler = match JobScheduler::new().await {
+            Ok(scheduler) => scheduler,
+            Err(e) => {
+                eprintln!("Failed to create screenshot scheduler: {}", e);
+                return;
+            }
+        };
+
+        for expression in &config.schedule.cron {
+            let schedule = expression.clone();
+            let job = Job::new_async_tz(expression.as_str(), Local, move |_uuid, _lock| {
+                Box::pin(run_scheduled_capture(schedule.clone()))
+            });
+            match job {
+                Ok(job) => {
+                    if let Err(e) = scheduler.add(job).await {
+                        eprintln!("Failed to add screenshot schedule {}: {}", expression, e);
+                    }
+                }
+                Err(e) => eprintln!("Invalid screenshot schedule {}: {}", expression, e),
+            }
+        }
+
+        if let Some(expression) = &config.timelapse.end_of_day {
+            let job = Job::new_async_tz(expression.as_str(), Local, |_uuid, _lock| {
+                Box::pin(async {
+                    let today = Local::now().date_naive();
+                    match task::spawn_blocking(move || generate_timelapse_for(today)).await {
+                        Ok(Ok(timelapse)) => println!("Timelapse saved: {}", serde_json::to_string(&timelapse).unwrap_or_default()),
+                        Ok(Err(e)) => eprintln!("Failed to generate timelapse: {}", e),
+                        Err(e) => eprintln!("Timelapse task failed: {}", e),
+                    }
+                })
+            });
+            match job {
+                Ok(job) => {
+                    if let Err(e) = scheduler.add(job).await {
+                        eprintln!("Failed to add timelapse schedule {}: {}", expression, e);
+                    }
+                }
+                Err(e) => eprintln!("Invalid timelapse schedule {}: {}", expression, e),
             }
         }
+
+        if let Err(e) = scheduler.start().await {
+            eprintln!("Failed to start screenshot scheduler: {}", e);
+            return;
+        }
+        // Keep the scheduler alive for the lifetime of the app
+        std::future::pending::<()>().await;
     });
 }
@@ -1,9 +1,28 @@
 pub mod system;
 pub mod installed_apps;
 pub mod browser;
+pub mod browser_categories;
+pub mod browser_extensions;
+pub mod browser_redaction;
+pub mod browser_search_terms;
+pub mod browser_sessions;
+pub mod browser_snapshot;
 pub mod running_apps;
 pub mod visible_apps;
 pub mod capture_screen;
+pub mod screenshot_activity;
+pub mod screenshot_crypto;
+pub mod screenshot_dedup;
+pub mod screenshot_evidence;
+pub mod screenshot_index;
+pub mod screenshot_masking;
+pub mod screenshot_overlay;
+pub mod screenshot_schedule;
+pub mod screenshot_storage;
+pub mod screenshot_timelapse;
+pub mod screenshot_triggers;
 pub mod usb_devices;
 pub mod usb_monitor;
-pub mod afk_tracker;
\ No newline at end of file
+pub mod afk_tracker;
+pub mod active_tab;
+pub mod private_browsing;
@@ -0,0 +1,178 @@
+use chrono::{DateTime, Duration, Local};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::Mutex;
+use crate::utils::db::connection;
+use crate::utils::time::format_duration;
+
+/// Window title suffixes of private windows, per browser executable. Matched only at the end
+/// of the title, where the browser puts them, so page titles such as "Private Equity News" do not count.
+const PRIVATE_WINDOW_SUFFIXES: &[(&str, &str, &[&str])] = &[
+    ("msedge.exe", "Edge", &[
+        "[InPrivate] - Microsoft\u{200b} Edge",
+        "[InPrivate] - Microsoft Edge",
+        "- InPrivate - Microsoft\u{200b} Edge",
+        "- InPrivate - Microsoft Edge",
+    ]),
+    ("chrome.exe", "Chrome", &["- Google Chrome (Incognito)", "- Incognito - Google Chrome"]),
+    ("brave.exe", "Brave", &["- Brave (Private)", "- Private - Brave"]),
+    ("firefox.exe", "Firefox", &[
+        "Mozilla Firefox Private Browsing",
+        "Private Browsing — Mozilla Firefox",
+        "Private Browsing - Mozilla Firefox",
+    ]),
+];
+
+/// A foreground gap longer than this ends the current private session.
+const SESSION_GAP_SECS: i64 = 5;
+
+lazy_static::lazy_static! {
+    static ref CURRENT_SESSION: Mutex<Option<PrivateSession>> = Mutex::new(None);
+}
+
+/// A private-browsing session. Only the browser and timing are kept, never titles or URLs.
+struct PrivateSession {
+    browser: &'static str,
+    start: DateTime<Local>,
+    last_seen: DateTime<Local>,
+    seconds: i64,
+}
+
+#[derive(Serialize)]
+pub struct PrivateBrowsingDay {
+    date: String,
+    sessions: i64,
+    time_seconds: i64,
+    time: String,
+}
+
+fn has_private_suffix(window_title: &str, suffixes: &[&str]) -> bool {
+    let window_title = window_title.trim_end();
+    suffixes.iter().any(|suffix| window_title.ends_with(suffix))
+}
+
+/// Cheap title-only check, so callers only look up the process name for likely matches.
+pub fn has_private_marker(window_title: &str) -> bool {
+    PRIVATE_WINDOW_SUFFIXES
+        .iter()
+        .any(|(_, _, suffixes)| has_private_suffix(window_title, suffixes))
+}
+
+/// Returns the browser name when the window is an InPrivate, Incognito or Private Browsing window.
+pub fn detect_private_window(process_name: &str, window_title: &str) -> Option<&'static str> {
+    PRIVATE_WINDOW_SUFFIXES
+        .iter()
+        .find(|(exe, _, suffixes)| exe.eq_ignore_ascii_case(process_name) && has_private_suffix(window_title, suffixes))
+        .map(|(_, browser, _)| *browser)
+}
+
+/// Private windows are tracked by time only, so their title is replaced with the browser name.
+/// Other titles are returned unchanged.
+pub fn mask_private_title(process_name: &str, window_title: String) -> String {
+    match detect_private_window(process_name, &window_title) {
+        Some(browser) => format!("Private browsing ({})", browser),
+        None => window_title,
+    }
+}
+
+pub(crate) fn ensure_table(conn: &Connection) {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS private_browsing_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            browser TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            duration_secs INTEGER NOT NULL
+        )",
+        [],
+    ).expect("Failed to create private_browsing_sessions table");
+}
+
+fn store_session(session: &PrivateSession) {
+    let conn = connection();
+
+    if let Err(err) = conn.execute(
+        "INSERT INTO private_browsing_sessions (browser, start_time, end_time, duration_secs)
+         VALUES (?, ?, ?, ?)",
+        params![
+            session.browser,
+            session.start.format("%Y-%m-%d %H:%M:%S").to_string(),
+            session.last_seen.format("%Y-%m-%d %H:%M:%S").to_string(),
+            session.seconds
+        ],
+    ) {
+        eprintln!("Failed to store private browsing session: {}", err);
+    }
+}
+
+/// Called once per foreground sample with the private browser in focus, if any.
+/// Consecutive samples extend the current session; anything else closes and stores it.
+pub fn track_private_foreground(browser: Option<&'static str>) {
+    let now = Local::now();
+    let mut current = CURRENT_SESSION.lock().unwrap();
+
+    let continues = match (current.as_ref(), browser) {
+        (Some(session), Some(browser)) => {
+            session.browser == browser && (now - session.last_seen).num_seconds() <= SESSION_GAP_SECS
+        }
+        _ => false,
+    };
+
+    if continues {
+        if let Some(session) = current.as_mut() {
+            session.seconds += 1;
+            session.last_seen = now;
+        }
+        return;
+    }
+
+    if let Some(finished) = current.take() {
+        store_session(&finished);
+    }
+    *current = browser.map(|browser| PrivateSession { browser, start: now, last_seen: now, seconds: 1 });
+}
+
+/// Returns total private-browsing time per day for the last `days` days (default 7),
+/// including the session that is still in progress.
+#[tauri::command]
+pub fn get_private_browsing_time(days: Option<u32>) -> String {
+    let days = days.unwrap_or(7).max(1) as i64;
+    let since = (Local::now().date_naive() - Duration::days(days - 1)).format("%Y-%m-%d").to_string();
+
+    let conn = connection();
+
+    let mut stmt = conn.prepare(
+        "SELECT substr(start_time, 1, 10) AS day, COUNT(*), SUM(duration_secs)
+         FROM private_browsing_sessions
+         WHERE start_time >= ?
+         GROUP BY day ORDER BY day"
+    ).expect("Failed to prepare query");
+
+    let mut totals: Vec<(String, i64, i64)> = stmt
+        .query_map([&since], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    if let Some(session) = CURRENT_SESSION.lock().unwrap().as_ref() {
+        let day = session.start.format("%Y-%m-%d").to_string();
+        match totals.iter_mut().find(|(date, _, _)| *date == day) {
+            Some(total) => {
+                total.1 += 1;
+                total.2 += session.seconds;
+            }
+            None => totals.push((day, 1, session.seconds)),
+        }
+    }
+
+    let report: Vec<PrivateBrowsingDay> = totals
+        .into_iter()
+        .map(|(date, sessions, time_seconds)| PrivateBrowsingDay {
+            date,
+            sessions,
+            time_seconds,
+            time: format_duration(time_seconds),
+        })
+        .collect();
+
+    serde_json::to_string(&report).unwrap_or_else(|_| "[]".to_string())
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use image::{imageops::FilterType, DynamicImage, GrayImage};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Captures are compared at this width so the score does not depend on resolution or format.
+const COMPARE_WIDTH: u32 = 480;
+/// Luma difference above which a pixel counts as changed; absorbs compression noise.
+const PIXEL_THRESHOLD: u8 = 24;
+/// Changed pixels are grouped into cells of this size before counting regions.
+const CELL_SIZE: u32 = 16;
+
+/// How much the screen changed since the previous capture of the same displays.
+#[derive(Serialize, Clone, Copy)]
+pub struct ScreenActivity {
+    /// Seconds since the previous capture.
+    pub(crate) interval_secs: i64,
+    /// Share of pixels that changed, 0.0–1.0.
+    pub(crate) changed_ratio: f64,
+    /// Number of separate changed areas, e.g. a video and a clock count as two.
+    pub(crate) changed_regions: u32,
+}
+
+lazy_static::lazy_static! {
+    /// Keyed by the ids of the displays the capture covers.
+    static ref LAST_FRAMES: Mutex<HashMap<String, (DateTime<Utc>, GrayImage)>> = Mutex::new(HashMap::new());
+}
+
+fn reduce(image: &DynamicImage) -> GrayImage {
+    let width = image.width().clamp(1, COMPARE_WIDTH);
+    let height = (image.height() as u64 * width as u64 / image.width().max(1) as u64).max(1) as u32;
+    image.resize_exact(width, height, FilterType::Triangle).to_luma8()
+}
+
+/// Counts 4-connected groups of changed cells.
+fn count_regions(cells: &mut [bool], columns: usize) -> u32 {
+    let rows = cells.len() / columns;
+    let mut regions = 0;
+    let mut stack = Vec::new();
+
+    for start in 0..cells.len() {
+        if !cells[start] {
+            continue;
+        }
+        regions += 1;
+        cells[start] = false;
+        stack.push(start);
+        while let Some(cell) = stack.pop() {
+            let (x, y) = (cell % columns, cell / columns);
+            let mut neighbours = Vec::with_capacity(4);
+            if x > 0 {
+                neighbours.push(cell - 1);
+            }
+            if x + 1 < columns {
+                neighbours.push(cell + 1);
+            }
+            if y > 0 {
+                neighbours.push(cell - columns);
+            }
+            if y + 1 < rows {
+                neighbours.push(cell + columns);
+            }
+            for neighbour in neighbours {
+                if cells[neighbour] {
+                    cells[neighbour] = false;
+                    stack.push(neighbour);
+                }
+            }
+        }
+    }
+    regions
+}
+
+fn compare(previous: &GrayImage, current: &GrayImage) -> (f64, u32) {
+    let columns = current.width().div_ceil(CELL_SIZE) as usize;
+    let rows = current.height().div_ceil(CELL_SIZE) as usize;
+    let mut cells = vec![false; columns * rows];
+    let mut changed = 0u64;
+
+    for (x, y, pixel) in current.enumerate_pixels() {
+        if pixel[0].abs_diff(previous.get_pixel(x, y)[0]) > PIXEL_THRESHOLD {
+            changed += 1;
+            cells[(y / CELL_SIZE) as usize * columns + (x / CELL_SIZE) as usize] = true;
+        }
+    }
+
+    let total = current.width() as u64 * current.height() as u64;
+    (changed as f64 / total.max(1) as f64, count_regions(&mut cells, columns))
+}
+
+/// Scores `image` against the previous capture of the same displays and keeps it for the next one.
+/// Returns `None` for the first capture, or when the display layout changed in between.
+pub fn measure_activity(displays_key: &str, captured_at: DateTime<Utc>, image: &DynamicImage) -> Option<ScreenActivity> {
+    let current = reduce(image);
+    let mut last_frames = LAST_FRAMES.lock().unwrap();
+
+    let activity = last_frames
+        .get(displays_key)
+        .filter(|(_, previous)| previous.dimensions() == current.dimensions())
+        .map(|(previous_at, previous)| {
+            let (changed_ratio, changed_regions) = compare(previous, &current);
+            ScreenActivity {
+                interval_secs: (captured_at - *previous_at).num_seconds(),
+                changed_ratio,
+                changed_regions,
+            }
+        });
+
+    last_frames.insert(displays_key.to_string(), (captured_at, current));
+    activity
+}
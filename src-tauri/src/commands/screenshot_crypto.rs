@@ -0,0 +1,198 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::Deserialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Header of encrypted files: magic, then the 12-byte nonce, then ciphertext with the GCM tag.
+const ENCRYPTED_MAGIC: &[u8; 4] = b"EMS1";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// Appended to the image file name, e.g. `screenshot-….jpg.enc`.
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+const KEYRING_SERVICE: &str = "ems-tauri";
+const KEYRING_USER: &str = "screenshot-encryption-key";
+
+lazy_static::lazy_static! {
+    /// The cipher of the key loaded last, with the `key_source` and `key_file` it was loaded for.
+    static ref CIPHER: Mutex<Option<((KeySource, String), Aes256Gcm)>> = Mutex::new(None);
+}
+
+/// Where a 32-byte key is kept; shared by the encryption and signing keys.
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Raw 32-byte key in `key_file`, created on first use. Anyone who can read the file can
+    /// decrypt the screenshots, so only use it with a path outside the working directory that
+    /// is restricted to the agent's account.
+    File,
+    /// Key stored in the OS credential store (Windows Credential Manager) for the agent's
+    /// account, created on first use. The default.
+    ///
+    /// For encryption, when the credential store cannot be used (e.g. an account without a
+    /// loaded profile) the key falls back to `key_file` and a warning is logged, so capturing
+    /// keeps working and screenshots are never written unencrypted. The key is moved into the
+    /// keyring once it becomes available.
+    Keyring,
+}
+
+/// The `encryption` section of `screenshot_config.json`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    pub(crate) enabled: bool,
+    key_source: KeySource,
+    /// The key for `key_source: "file"`. With the keyring, a key left here by an earlier
+    /// file setup is moved into the keyring on first use.
+    key_file: String,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            key_source: KeySource::Keyring,
+            key_file: "screenshot.key".to_string(),
+        }
+    }
+}
+
+fn generate_key() -> Vec<u8> {
+    Aes256Gcm::generate_key(OsRng).to_vec()
+}
+
+fn key_from_file(path: &str) -> Result<Vec<u8>, String> {
+    match fs::read(path) {
+        Ok(key) => Ok(key),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let key = generate_key();
+            // `create_new` so that two first captures racing cannot overwrite each other's key.
+            let mut file = OpenOptions::new().write(true).create_new(true).open(path).map_err(|e| e.to_string())?;
+            file.write_all(&key).map_err(|e| e.to_string())?;
+            Ok(key)
+        }
+        Err(err) => Err(format!("Failed to read key file {}: {}", path, err)),
+    }
+}
+
+/// Reads the key from the keyring. When there is none yet, a key in `legacy_file` is imported
+/// (so files encrypted or signed with it stay valid) and the file is deleted once the keyring
+/// returns it; otherwise a new key is generated.
+fn key_from_keyring(user: &str, legacy_file: &str) -> Result<Vec<u8>, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, user).map_err(|e| e.to_string())?;
+    match entry.get_secret() {
+        Ok(key) => Ok(key),
+        Err(keyring::Error::NoEntry) => {
+            let legacy_key = fs::read(legacy_file).ok();
+            let key = legacy_key.clone().unwrap_or_else(generate_key);
+            entry.set_secret(&key).map_err(|e| e.to_string())?;
+
+            if legacy_key.is_some() {
+                match entry.get_secret() {
+                    Ok(stored) if stored == key => match fs::remove_file(legacy_file) {
+                        Ok(()) => println!("Moved key {} into the keyring", legacy_file),
+                        Err(err) => eprintln!("Key {} is in the keyring but could not be deleted: {}", legacy_file, err),
+                    },
+                    _ => eprintln!("Keeping key file {}: the keyring did not return the imported key", legacy_file),
+                }
+            }
+            Ok(key)
+        }
+        Err(err) => Err(format!("Failed to read key from keyring: {}", err)),
+    }
+}
+
+/// Loads a 32-byte key from `key_file` or from the keyring entry `keyring_user`, creating it on first use.
+pub(crate) fn load_key(source: KeySource, key_file: &str, keyring_user: &str) -> Result<[u8; KEY_LEN], String> {
+    let (key, name) = match source {
+        KeySource::File => (key_from_file(key_file)?, key_file),
+        KeySource::Keyring => (key_from_keyring(keyring_user, key_file)?, keyring_user),
+    };
+    let len = key.len();
+    key.try_into().map_err(|_| format!("Key {} must be {} bytes, found {}", name, KEY_LEN, len))
+}
+
+impl EncryptionConfig {
+    /// Falls back from the keyring to `key_file`; see [`KeySource::Keyring`].
+    fn load_key(&self) -> Result<[u8; KEY_LEN], String> {
+        match load_key(self.key_source, &self.key_file, KEYRING_USER) {
+            Err(err) if self.key_source == KeySource::Keyring => {
+                eprintln!("Screenshot key unavailable in the keyring ({}), using key file {}", err, self.key_file);
+                load_key(KeySource::File, &self.key_file, KEYRING_USER)
+            }
+            result => result,
+        }
+    }
+
+    /// Returns the cipher. The key is loaded, and created on first use, only once per
+    /// `key_source` and `key_file`; if neither the keyring nor the file works, every call fails
+    /// and nothing is captured.
+    pub fn cipher(&self) -> Result<Aes256Gcm, String> {
+        let mut cached = CIPHER.lock().unwrap();
+        let loaded_for = (self.key_source, self.key_file.clone());
+        if let Some((_, cipher)) = cached.as_ref().filter(|(cached_for, _)| *cached_for == loaded_for) {
+            return Ok(cipher.clone());
+        }
+        let key = self.load_key()?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        *cached = Some((loaded_for, cipher.clone()));
+        Ok(cipher)
+    }
+}
+
+/// The cipher loaded last by [`EncryptionConfig::cipher`], if any.
+pub fn cached_cipher() -> Option<Aes256Gcm> {
+    CIPHER.lock().unwrap().as_ref().map(|(_, cipher)| cipher.clone())
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_MAGIC)
+}
+
+/// Encrypts with a fresh random nonce.
+pub fn encrypt(cipher: &Aes256Gcm, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, plaintext).map_err(|_| "Failed to encrypt screenshot".to_string())?;
+
+    let mut data = Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(ENCRYPTED_MAGIC);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypts and authenticates data written by [`encrypt`]; fails if it was modified.
+pub fn decrypt(cipher: &Aes256Gcm, data: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_encrypted(data) || data.len() < ENCRYPTED_MAGIC.len() + NONCE_LEN {
+        return Err("Not an encrypted screenshot".to_string());
+    }
+    let (nonce, ciphertext) = data[ENCRYPTED_MAGIC.len()..].split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Screenshot could not be decrypted: wrong key or file modified".to_string())
+}
+
+/// Returns `path` with the encrypted extension appended.
+pub fn encrypted_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ENCRYPTED_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Encrypts one plain file next to itself and removes the original.
+/// The encrypted copy is written under a temporary name first so an interrupted run leaves no partial file.
+pub fn encrypt_file(cipher: &Aes256Gcm, path: &Path) -> Result<PathBuf, String> {
+    let plaintext = fs::read(path).map_err(|e| e.to_string())?;
+    let target = encrypted_path(path);
+    let partial = target.with_extension(format!("{}.partial", ENCRYPTED_EXTENSION));
+
+    fs::write(&partial, encrypt(cipher, &plaintext)?).map_err(|e| e.to_string())?;
+    fs::rename(&partial, &target).map_err(|e| e.to_string())?;
+    fs::remove_file(path).map_err(|e| e.to_string())?;
+    Ok(target)
+}
@@ -0,0 +1,105 @@
+use image::{imageops::FilterType, DynamicImage};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// The hash compares each cell of a `HASH_SIZE` x `HASH_SIZE` grid with its right neighbour.
+/// A finer grid than the usual 8x8 is used because screens differ mostly in small text.
+const HASH_SIZE: u32 = 16;
+
+/// Difference hash of a capture, one bit per grid cell.
+pub type PerceptualHash = [u8; (HASH_SIZE * HASH_SIZE / 8) as usize];
+
+/// SHA-256 of a capture's dimensions and raw pixels.
+pub type PixelHash = [u8; 32];
+
+/// How close a capture has to be to the previous one to be stored as a reference to it.
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateMatch {
+    /// Perceptual hashes differ by at most `duplicate_threshold` bits, so a ticking clock or a
+    /// blinking cursor does not count as a change. The default.
+    Perceptual,
+    /// Only pixel-identical captures. Keeps every visible change, but a live desktop rarely
+    /// repeats exactly, so little storage is saved.
+    Exact,
+}
+
+/// The last capture that was written to disk for a set of displays.
+#[derive(Clone)]
+pub struct StoredCapture {
+    pub id: i64,
+    pub path: String,
+    pub format: &'static str,
+    pub content_hash: String,
+    pub thumbnail_path: Option<String>,
+    pub hash: PerceptualHash,
+    pub pixels: PixelHash,
+}
+
+lazy_static::lazy_static! {
+    /// Keyed by the ids of the displays the capture covers.
+    static ref LAST_CAPTURES: Mutex<HashMap<String, StoredCapture>> = Mutex::new(HashMap::new());
+}
+
+/// Computes a difference hash (dHash) of the image.
+pub fn perceptual_hash(image: &DynamicImage) -> PerceptualHash {
+    let small = image.resize_exact(HASH_SIZE + 1, HASH_SIZE, FilterType::Triangle).to_luma8();
+    let mut hash = [0u8; (HASH_SIZE * HASH_SIZE / 8) as usize];
+
+    for y in 0..HASH_SIZE {
+        for x in 0..HASH_SIZE {
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                let bit = (y * HASH_SIZE + x) as usize;
+                hash[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+    }
+    hash
+}
+
+pub fn hash_to_hex(hash: &PerceptualHash) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Hashes the image before it is watermarked or encoded, so equal hashes mean equal pixels.
+pub fn pixel_hash(image: &DynamicImage) -> PixelHash {
+    let mut hasher = Sha256::new();
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(image.as_bytes());
+    hasher.finalize().into()
+}
+
+fn hamming_distance(a: &PerceptualHash, b: &PerceptualHash) -> u32 {
+    a.iter().zip(b.iter()).map(|(a, b)| (a ^ b).count_ones()).sum()
+}
+
+/// Returns the last stored capture of the same displays when it matches the new capture under
+/// `mode` and its file still exists (the quota may have removed it). In `Perceptual` mode the
+/// hashes may differ by at most `max_distance` bits.
+pub fn find_duplicate(
+    displays_key: &str,
+    hash: &PerceptualHash,
+    pixels: &PixelHash,
+    mode: DuplicateMatch,
+    max_distance: u32,
+) -> Option<StoredCapture> {
+    LAST_CAPTURES
+        .lock()
+        .unwrap()
+        .get(displays_key)
+        .filter(|previous| match mode {
+            DuplicateMatch::Perceptual => hamming_distance(&previous.hash, hash) <= max_distance,
+            DuplicateMatch::Exact => previous.pixels == *pixels,
+        })
+        .filter(|previous| Path::new(&previous.path).exists())
+        .cloned()
+}
+
+/// Makes `capture` the reference that later captures of the same displays are compared with.
+pub fn remember_capture(displays_key: String, capture: StoredCapture) {
+    LAST_CAPTURES.lock().unwrap().insert(displays_key, capture);
+}
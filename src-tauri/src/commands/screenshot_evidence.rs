@@ -0,0 +1,249 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use sysinfo::System;
+use super::capture_screen::{load_screenshot_config, read_screenshot_file};
+use super::screenshot_crypto::{load_key, KeySource};
+use super::screenshot_index::screenshot_file;
+use super::screenshot_overlay::{draw_label, Corner};
+
+/// Appended to the image file name, e.g. `screenshot-….jpg.enc.sig`.
+pub const SIGNATURE_EXTENSION: &str = "sig";
+const SIGNATURE_VERSION: u32 = 1;
+const KEYRING_USER: &str = "screenshot-signing-key";
+
+/// The `evidence` section of `screenshot_config.json`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct EvidenceConfig {
+    /// Burn the agent ID, user and UTC time into the bottom-right corner of each screenshot.
+    pub(crate) watermark: bool,
+    /// Write a detached signature next to each screenshot.
+    pub(crate) sign: bool,
+    /// Identifies this machine in watermarks and signatures; defaults to the host name.
+    agent_id: Option<String>,
+    /// Where the agent's Ed25519 signing key is kept; created on first use. Defaults to the
+    /// keyring: a key file can be read by anyone with access to the working directory, who could
+    /// then sign altered screenshots.
+    key_source: KeySource,
+    /// The key for `key_source: "file"`; with the keyring, a key left here is moved into it.
+    key_file: String,
+}
+
+impl Default for EvidenceConfig {
+    fn default() -> Self {
+        Self {
+            watermark: false,
+            sign: true,
+            agent_id: None,
+            key_source: KeySource::Keyring,
+            key_file: "screenshot_signing.key".to_string(),
+        }
+    }
+}
+
+/// Detached signature, stored as JSON in `<image>.sig`. The signature covers the plaintext
+/// image hash, so it stays valid when the file is encrypted or moved.
+#[derive(Serialize, Deserialize)]
+struct SignatureFile {
+    version: u32,
+    agent_id: String,
+    user: String,
+    /// UTC, RFC 3339.
+    captured_at: String,
+    /// SHA-256 of the image before encryption, hex encoded.
+    content_hash: String,
+    /// The agent's Ed25519 public key, base64.
+    public_key: String,
+    /// Ed25519 signature over the fields above, base64.
+    signature: String,
+}
+
+impl SignatureFile {
+    fn signed_message(&self) -> String {
+        format!(
+            "ems-screenshot-v{}\n{}\n{}\n{}\n{}\n{}",
+            self.version, self.agent_id, self.user, self.captured_at, self.content_hash, self.public_key
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct VerificationResult {
+    id: i64,
+    path: String,
+    valid: bool,
+    /// Why verification failed; `None` when valid.
+    reason: Option<String>,
+    agent_id: Option<String>,
+    user: Option<String>,
+    captured_at: Option<String>,
+    /// The key the signature was checked against: the pinned key when one was given,
+    /// otherwise this agent's current key.
+    public_key: Option<String>,
+}
+
+/// The agent's public key, to pin outside the agent (e.g. with the evidence register) and pass
+/// back to `verify_screenshot`.
+#[derive(Serialize)]
+pub struct SigningPublicKey {
+    agent_id: String,
+    /// Ed25519 public key, base64.
+    public_key: String,
+    /// SHA-256 of the raw public key, hex encoded, for comparing keys by eye.
+    fingerprint: String,
+}
+
+/// The signed-in Windows user.
+fn current_user() -> String {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+impl EvidenceConfig {
+    fn agent_id(&self) -> String {
+        self.agent_id
+            .clone()
+            .or_else(System::host_name)
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    fn signing_key(&self) -> Result<SigningKey, String> {
+        Ok(SigningKey::from_bytes(&load_key(self.key_source, &self.key_file, KEYRING_USER)?))
+    }
+
+    /// Returns a copy of the image with the agent ID, user and capture time in the bottom-right corner.
+    pub fn watermark(&self, image: &DynamicImage, captured_at: DateTime<Utc>) -> DynamicImage {
+        let lines = [
+            format!("Agent: {}", self.agent_id()),
+            format!("User: {}", current_user()),
+            format!("{} UTC", captured_at.format("%Y-%m-%d %H:%M:%S")),
+        ];
+        let mut image = image.to_rgba8();
+        draw_label(&mut image, &lines, Corner::BottomRight);
+        DynamicImage::ImageRgba8(image)
+    }
+
+    /// Signs a saved screenshot, given the SHA-256 of its plaintext, and writes the signature next to it.
+    pub fn sign_screenshot(&self, path: &Path, content_hash: &str, captured_at: DateTime<Utc>) -> Result<PathBuf, String> {
+        let key = self.signing_key()?;
+        let mut signature = SignatureFile {
+            version: SIGNATURE_VERSION,
+            agent_id: self.agent_id(),
+            user: current_user(),
+            captured_at: captured_at.to_rfc3339(),
+            content_hash: content_hash.to_string(),
+            public_key: STANDARD.encode(key.verifying_key().to_bytes()),
+            signature: String::new(),
+        };
+        signature.signature = STANDARD.encode(key.sign(signature.signed_message().as_bytes()).to_bytes());
+
+        let signature_path = signature_path(path);
+        let json = serde_json::to_string_pretty(&signature).map_err(|e| e.to_string())?;
+        fs::write(&signature_path, json).map_err(|e| e.to_string())?;
+        Ok(signature_path)
+    }
+
+    /// Checks that the signature was made with `public_key`, matches its metadata,
+    /// and that the image still hashes to the signed value.
+    fn verify(&self, path: &Path, public_key: &VerifyingKey) -> Result<SignatureFile, String> {
+        let json = fs::read_to_string(signature_path(path)).map_err(|_| "Screenshot is not signed".to_string())?;
+        let signature: SignatureFile =
+            serde_json::from_str(&json).map_err(|e| format!("Signature file is unreadable: {}", e))?;
+
+        if signature.public_key != STANDARD.encode(public_key.to_bytes()) {
+            return Err("Signed with a different key than the one it was checked against".to_string());
+        }
+        let signature_bytes: [u8; 64] = STANDARD
+            .decode(&signature.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "Signature is malformed".to_string())?;
+        public_key
+            .verify(signature.signed_message().as_bytes(), &Signature::from_bytes(&signature_bytes))
+            .map_err(|_| "Signature does not match its metadata".to_string())?;
+
+        // Decryption authenticates encrypted files as well
+        let image = read_screenshot_file(path)?;
+        if format!("{:x}", Sha256::digest(&image)) != signature.content_hash {
+            return Err("Image was modified after it was signed".to_string());
+        }
+        Ok(signature)
+    }
+}
+
+/// Returns `path` with the signature extension appended.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(SIGNATURE_EXTENSION);
+    PathBuf::from(name)
+}
+
+fn parse_public_key(public_key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = STANDARD
+        .decode(public_key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Public key must be 32 bytes, base64 encoded".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))
+}
+
+/// Returns this agent's signing public key, creating the key pair on first use.
+#[tauri::command]
+pub fn get_signing_public_key() -> Result<String, String> {
+    let evidence = load_screenshot_config().evidence;
+    let public_key = evidence.signing_key()?.verifying_key().to_bytes();
+    let exported = SigningPublicKey {
+        agent_id: evidence.agent_id(),
+        public_key: STANDARD.encode(public_key),
+        fingerprint: format!("{:x}", Sha256::digest(public_key)),
+    };
+    serde_json::to_string(&exported).map_err(|e| e.to_string())
+}
+
+/// Verifies the detached signature of a screenshot; `valid` is false with a `reason` when it was
+/// altered, is unsigned, or was signed with another key.
+///
+/// `public_key` is a key pinned earlier from `get_signing_public_key`. Without it the agent's
+/// current key is used, which cannot detect a replaced key and re-signed files.
+#[tauri::command]
+pub fn verify_screenshot(id: i64, public_key: Option<String>) -> Result<String, String> {
+    let (path, _) = screenshot_file(id).ok_or_else(|| format!("Screenshot {} not found", id))?;
+    let evidence = load_screenshot_config().evidence;
+    let verifying_key = match public_key {
+        Some(public_key) => parse_public_key(&public_key)?,
+        None => evidence.signing_key()?.verifying_key(),
+    };
+    let public_key = Some(STANDARD.encode(verifying_key.to_bytes()));
+
+    let result = match evidence.verify(Path::new(&path), &verifying_key) {
+        Ok(signature) => VerificationResult {
+            id,
+            path,
+            valid: true,
+            reason: None,
+            agent_id: Some(signature.agent_id),
+            user: Some(signature.user),
+            captured_at: Some(signature.captured_at),
+            public_key,
+        },
+        Err(reason) => VerificationResult {
+            id,
+            path,
+            valid: false,
+            reason: Some(reason),
+            agent_id: None,
+            user: None,
+            captured_at: None,
+            public_key,
+        },
+    };
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
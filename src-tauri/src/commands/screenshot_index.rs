@@ -0,0 +1,486 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use tokio::task;
+use crate::utils::db::connection;
+use crate::utils::time::local_date_range;
+use chrono::{DateTime, Utc};
+use super::capture_screen::{load_screenshot_config, make_thumbnail, read_screenshot_file, CaptureContext, DisplayMeta, SavedScreenshot};
+use super::screenshot_activity::ScreenActivity;
+
+/// Screenshots returned per `list_screenshots` page.
+const PAGE_SIZE: u32 = 50;
+/// Thumbnails returned per `get_screenshot_gallery` page.
+const GALLERY_PAGE_SIZE: u32 = 24;
+
+#[derive(Serialize)]
+pub struct ScreenshotEntry {
+    id: i64,
+    path: String,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    captured_at: String,
+    displays: Value,
+    window_title: Option<String>,
+    process_name: Option<String>,
+    afk: bool,
+    width: u32,
+    height: u32,
+    format: String,
+    bytes: u64,
+    content_hash: String,
+    /// Set when the capture was unchanged and reuses the file of that screenshot.
+    duplicate_of: Option<i64>,
+    /// What caused the capture, e.g. `{"kind": "usb_storage", "drive": "E:\\"}`; null for older rows.
+    trigger: Value,
+}
+
+#[derive(Serialize)]
+pub struct ScreenshotImage {
+    id: i64,
+    format: String,
+    /// The decrypted image as a `data:` URL, ready for an `<img>` element.
+    data_url: String,
+}
+
+#[derive(Serialize)]
+pub struct GalleryItem {
+    id: i64,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    captured_at: String,
+    window_title: Option<String>,
+    process_name: Option<String>,
+    /// JPEG thumbnail as a `data:` URL; `None` if neither the thumbnail nor the screenshot could be read.
+    thumbnail: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct GalleryPage {
+    page: u32,
+    page_size: u32,
+    total: i64,
+    items: Vec<GalleryItem>,
+}
+
+#[derive(Serialize)]
+pub struct ActivityInterval {
+    /// UTC, `YYYY-MM-DD HH:MM:SS`; the interval ends at this capture.
+    captured_at: String,
+    interval_secs: i64,
+    changed_ratio: f64,
+    changed_regions: u32,
+    /// Whether the keyboard/mouse tracker considered the user away at the time.
+    afk: bool,
+    /// Scored from an unsaved sample taken while the user was away, so there is no screenshot.
+    sampled: bool,
+    displays: Value,
+}
+
+#[derive(Serialize)]
+pub struct ScreenshotPage {
+    page: u32,
+    page_size: u32,
+    total: i64,
+    screenshots: Vec<ScreenshotEntry>,
+}
+
+pub(crate) fn ensure_table(conn: &Connection) {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS screenshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            captured_at TEXT NOT NULL,
+            captured_ts INTEGER NOT NULL,
+            displays TEXT NOT NULL,
+            window_title TEXT,
+            process_name TEXT,
+            afk INTEGER NOT NULL,
+            width INTEGER NOT NULL,
+            height INTEGER NOT NULL,
+            format TEXT NOT NULL,
+            bytes INTEGER NOT NULL,
+            content_hash TEXT NOT NULL
+        )",
+        [],
+    ).expect("Failed to create screenshots table");
+    ensure_column(conn, "perceptual_hash", "TEXT");
+    ensure_column(conn, "duplicate_of", "INTEGER REFERENCES screenshots (id)");
+    ensure_column(conn, "thumbnail_path", "TEXT");
+    ensure_column(conn, "activity_interval_secs", "INTEGER");
+    ensure_column(conn, "changed_ratio", "REAL");
+    ensure_column(conn, "changed_regions", "INTEGER");
+    ensure_column(conn, "capture_trigger", "TEXT");
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_screenshots_captured_ts ON screenshots (captured_ts)",
+        [],
+    ).expect("Failed to create screenshots index");
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS activity_samples (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            captured_at TEXT NOT NULL,
+            captured_ts INTEGER NOT NULL,
+            displays TEXT NOT NULL,
+            interval_secs INTEGER NOT NULL,
+            changed_ratio REAL NOT NULL,
+            changed_regions INTEGER NOT NULL
+        )",
+        [],
+    ).expect("Failed to create activity_samples table");
+}
+
+/// Adds a column that was introduced after the table was first created.
+fn ensure_column(conn: &Connection, column: &str, definition: &str) {
+    let exists = conn
+        .prepare("SELECT 1 FROM pragma_table_info('screenshots') WHERE name = ?")
+        .and_then(|mut stmt| stmt.exists([column]))
+        .unwrap_or(false);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE screenshots ADD COLUMN {} {}", column, definition), [])
+            .expect("Failed to migrate screenshots table");
+    }
+}
+
+/// Stores the metadata of a saved screenshot and returns its row id.
+pub fn record_screenshot(saved: &SavedScreenshot, context: &CaptureContext) -> Result<i64, String> {
+    let conn = connection();
+
+    conn.execute(
+        "INSERT INTO screenshots (path, captured_at, captured_ts, displays, window_title, process_name,
+                                  afk, width, height, format, bytes, content_hash, perceptual_hash, duplicate_of,
+                                  thumbnail_path, activity_interval_secs, changed_ratio, changed_regions,
+                                  capture_trigger)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            saved.path,
+            context.captured_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            context.captured_at.timestamp(),
+            serde_json::to_string(&saved.displays).unwrap_or_else(|_| "[]".to_string()),
+            context.window_title,
+            context.process_name,
+            context.afk,
+            saved.width,
+            saved.height,
+            saved.format,
+            saved.bytes,
+            saved.content_hash,
+            saved.perceptual_hash,
+            saved.duplicate_of,
+            saved.thumbnail_path,
+            saved.activity.map(|activity| activity.interval_secs),
+            saved.activity.map(|activity| activity.changed_ratio),
+            saved.activity.map(|activity| activity.changed_regions),
+            serde_json::to_string(&context.trigger).unwrap_or_else(|_| "null".to_string()),
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// Stores the score of an unsaved sample taken while the user was away.
+pub fn record_activity_sample(captured_at: DateTime<Utc>, displays: &[DisplayMeta], activity: &ScreenActivity) -> Result<(), String> {
+    let conn = connection();
+    conn.execute(
+        "INSERT INTO activity_samples (captured_at, captured_ts, displays, interval_secs, changed_ratio, changed_regions)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        params![
+            captured_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            captured_at.timestamp(),
+            serde_json::to_string(displays).unwrap_or_else(|_| "[]".to_string()),
+            activity.interval_secs,
+            activity.changed_ratio,
+            activity.changed_regions,
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Points index rows at a file's new location, e.g. after it was encrypted. The file may be
+/// a screenshot or a thumbnail.
+pub fn update_screenshot_path(old_path: &str, new_path: &str) -> Result<usize, String> {
+    let conn = connection();
+    let screenshots = conn
+        .execute("UPDATE screenshots SET path = ? WHERE path = ?", [new_path, old_path])
+        .map_err(|e| e.to_string())?;
+    let thumbnails = conn
+        .execute("UPDATE screenshots SET thumbnail_path = ? WHERE thumbnail_path = ?", [new_path, old_path])
+        .map_err(|e| e.to_string())?;
+    Ok(screenshots + thumbnails)
+}
+
+/// Deletes the rows whose `captured_ts` matches `condition` and returns how many were deleted,
+/// with the screenshot and thumbnail files that no remaining row refers to.
+/// Deduplicated captures share files, so a file is only released with its last row.
+fn delete_rows(conn: &Connection, condition: &str, captured_ts: i64) -> Result<(usize, Vec<String>), String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT path, thumbnail_path FROM screenshots WHERE captured_ts {} ?", condition))
+        .map_err(|e| e.to_string())?;
+    let mut files: Vec<String> = stmt
+        .query_map([captured_ts], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .flat_map(|(path, thumbnail_path)| std::iter::once(path).chain(thumbnail_path))
+        .collect();
+    files.sort();
+    files.dedup();
+
+    let rows = conn
+        .execute(&format!("DELETE FROM screenshots WHERE captured_ts {} ?", condition), [captured_ts])
+        .map_err(|e| e.to_string())?;
+
+    let mut referenced = conn
+        .prepare("SELECT 1 FROM screenshots WHERE path = ?1 OR thumbnail_path = ?1")
+        .map_err(|e| e.to_string())?;
+    files.retain(|file| !referenced.exists([file]).unwrap_or(true));
+    Ok((rows, files))
+}
+
+/// Deletes the index rows of screenshots, and the activity samples, captured before `captured_ts`.
+pub(crate) fn delete_screenshots_before(captured_ts: i64) -> Result<(usize, Vec<String>), String> {
+    let conn = connection();
+    conn.execute("DELETE FROM activity_samples WHERE captured_ts < ?", [captured_ts])
+        .map_err(|e| e.to_string())?;
+    delete_rows(&conn, "<", captured_ts)
+}
+
+/// Deletes the index rows of the oldest capture (all its displays); `(0, [])` when none are left.
+pub(crate) fn delete_oldest_capture() -> Result<(usize, Vec<String>), String> {
+    let conn = connection();
+    let oldest: Option<i64> = conn
+        .query_row("SELECT MIN(captured_ts) FROM screenshots", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    match oldest {
+        Some(captured_ts) => delete_rows(&conn, "=", captured_ts),
+        None => Ok((0, Vec::new())),
+    }
+}
+
+/// Returns the number of indexed screenshots and the first and last capture times (UTC).
+pub(crate) fn index_summary() -> (i64, Option<String>, Option<String>) {
+    let conn = connection();
+    conn.query_row("SELECT COUNT(*), MIN(captured_at), MAX(captured_at) FROM screenshots", [], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    })
+    .unwrap_or((0, None, None))
+}
+
+/// Returns the path and format of a screenshot's file.
+pub(crate) fn screenshot_file(id: i64) -> Option<(String, String)> {
+    let conn = connection();
+    conn.query_row("SELECT path, format FROM screenshots WHERE id = ?", [id], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })
+    .ok()
+}
+
+/// Returns (path, capture time) of the screenshots in `[from_ts, to_ts)`, oldest first,
+/// one per capture: the first file when a capture was saved per display.
+pub(crate) fn screenshots_between(from_ts: i64, to_ts: i64) -> Vec<(String, i64)> {
+    let conn = connection();
+    let mut stmt = conn.prepare(
+        "SELECT path, captured_ts FROM screenshots
+         WHERE id IN (SELECT MIN(id) FROM screenshots WHERE captured_ts >= ? AND captured_ts < ? GROUP BY captured_ts)
+         ORDER BY captured_ts"
+    ).expect("Failed to prepare query");
+
+    stmt.query_map([from_ts, to_ts], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default()
+}
+
+pub(crate) fn mime_type(format: &str) -> &'static str {
+    match format {
+        "png" => "image/png",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        _ => "image/jpeg",
+    }
+}
+
+/// Returns one screenshot, decrypted, for the viewer.
+#[tauri::command]
+pub fn get_screenshot(id: i64) -> Result<String, String> {
+    let (path, format) = screenshot_file(id).ok_or_else(|| format!("Screenshot {} not found", id))?;
+    let image = read_screenshot_file(Path::new(&path))?;
+
+    let screenshot = ScreenshotImage {
+        id,
+        data_url: format!("data:{};base64,{}", mime_type(&format), STANDARD.encode(image)),
+        format,
+    };
+    serde_json::to_string(&screenshot).map_err(|e| e.to_string())
+}
+
+/// Lists screenshots taken between `from` and `to` (inclusive local `YYYY-MM-DD` dates),
+/// newest first, `PAGE_SIZE` per page. Pages start at 1.
+#[tauri::command]
+pub fn list_screenshots(from: Option<String>, to: Option<String>, page: Option<u32>) -> String {
+    let (from_ts, to_ts) = local_date_range(from, to);
+    let page = page.unwrap_or(1).max(1);
+
+    let conn = connection();
+
+    let total: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM screenshots WHERE captured_ts >= ? AND captured_ts < ?",
+            [from_ts, to_ts],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let mut stmt = conn.prepare(
+        "SELECT id, path, captured_at, displays, window_title, process_name, afk,
+                width, height, format, bytes, content_hash, duplicate_of, capture_trigger
+         FROM screenshots
+         WHERE captured_ts >= ? AND captured_ts < ?
+         ORDER BY captured_ts DESC, id DESC
+         LIMIT ? OFFSET ?"
+    ).expect("Failed to prepare query");
+
+    let screenshots: Vec<ScreenshotEntry> = stmt
+        .query_map(params![from_ts, to_ts, PAGE_SIZE, (page - 1) * PAGE_SIZE], |row| {
+            let displays: String = row.get(3)?;
+            let trigger: Option<String> = row.get(13)?;
+            Ok(ScreenshotEntry {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                captured_at: row.get(2)?,
+                displays: serde_json::from_str(&displays).unwrap_or(Value::Null),
+                window_title: row.get(4)?,
+                process_name: row.get(5)?,
+                afk: row.get(6)?,
+                width: row.get(7)?,
+                height: row.get(8)?,
+                format: row.get(9)?,
+                bytes: row.get(10)?,
+                content_hash: row.get(11)?,
+                duplicate_of: row.get(12)?,
+                trigger: trigger.and_then(|trigger| serde_json::from_str(&trigger).ok()).unwrap_or(Value::Null),
+            })
+        })
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    serde_json::to_string(&ScreenshotPage { page, page_size: PAGE_SIZE, total, screenshots })
+        .unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Reads the saved thumbnail, or scales down the full screenshot when there is none
+/// (screenshots taken before thumbnails were introduced). Generated thumbnails are not saved.
+fn thumbnail_data_url(thumbnail_path: Option<&str>, path: &str, size: u32) -> Result<String, String> {
+    let thumbnail = match thumbnail_path.map(|thumbnail| read_screenshot_file(Path::new(thumbnail))) {
+        Some(Ok(thumbnail)) => thumbnail,
+        _ => {
+            let image = read_screenshot_file(Path::new(path))?;
+            let image = image::load_from_memory(&image).map_err(|e| e.to_string())?;
+            make_thumbnail(&image, size)?
+        }
+    };
+    Ok(format!("data:image/jpeg;base64,{}", STANDARD.encode(thumbnail)))
+}
+
+/// Returns thumbnails of the screenshots taken between `from` and `to` (inclusive local
+/// `YYYY-MM-DD` dates), newest first, `GALLERY_PAGE_SIZE` per page. Pages start at 1.
+/// Duplicate captures are left out since they show the same image as an earlier one.
+#[tauri::command]
+pub async fn get_screenshot_gallery(from: Option<String>, to: Option<String>, page: Option<u32>) -> String {
+    let (from_ts, to_ts) = local_date_range(from, to);
+    let page = page.unwrap_or(1).max(1);
+    let size = load_screenshot_config().thumbnail_size;
+
+    let gallery = task::spawn_blocking(move || {
+        let conn = connection();
+
+        let total: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM screenshots
+                 WHERE captured_ts >= ? AND captured_ts < ? AND duplicate_of IS NULL",
+                [from_ts, to_ts],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+
+        let mut stmt = conn.prepare(
+            "SELECT id, captured_at, window_title, process_name, path, thumbnail_path
+             FROM screenshots
+             WHERE captured_ts >= ? AND captured_ts < ? AND duplicate_of IS NULL
+             ORDER BY captured_ts DESC, id DESC
+             LIMIT ? OFFSET ?"
+        ).expect("Failed to prepare query");
+
+        let rows: Vec<(GalleryItem, String, Option<String>)> = stmt
+            .query_map(params![from_ts, to_ts, GALLERY_PAGE_SIZE, (page - 1) * GALLERY_PAGE_SIZE], |row| {
+                let item = GalleryItem {
+                    id: row.get(0)?,
+                    captured_at: row.get(1)?,
+                    window_title: row.get(2)?,
+                    process_name: row.get(3)?,
+                    thumbnail: None,
+                };
+                Ok((item, row.get(4)?, row.get(5)?))
+            })
+            .map(|rows| rows.filter_map(Result::ok).collect())
+            .unwrap_or_default();
+        drop(stmt);
+        drop(conn);
+
+        // Files are read after the lock is released so other commands are not held up
+        let items = rows
+            .into_iter()
+            .map(|(mut item, path, thumbnail_path)| {
+                item.thumbnail = thumbnail_data_url(thumbnail_path.as_deref(), &path, size)
+                    .map_err(|e| eprintln!("Failed to load thumbnail for screenshot {}: {}", item.id, e))
+                    .ok();
+                item
+            })
+            .collect();
+
+        GalleryPage { page, page_size: GALLERY_PAGE_SIZE, total, items }
+    })
+    .await;
+
+    match gallery {
+        Ok(gallery) => serde_json::to_string(&gallery).unwrap_or_else(|_| "{}".to_string()),
+        Err(_) => "{}".to_string(),
+    }
+}
+
+/// Returns the screen-change score of each interval between consecutive captures taken between
+/// `from` and `to` (inclusive local `YYYY-MM-DD` dates), oldest first. Scheduled captures are
+/// skipped while the user is away (`skip_when_idle`), so those intervals come from unsaved
+/// samples (`sampled`). A high change ratio while `afk` is set points to someone watching the
+/// screen without typing, e.g. reviewing a video.
+#[tauri::command]
+pub fn get_screen_activity(from: Option<String>, to: Option<String>) -> String {
+    let (from_ts, to_ts) = local_date_range(from, to);
+
+    let conn = connection();
+    let mut stmt = conn.prepare(
+        "SELECT captured_at, activity_interval_secs, changed_ratio, changed_regions, afk, 0, displays, captured_ts, id
+         FROM screenshots
+         WHERE captured_ts >= ?1 AND captured_ts < ?2 AND changed_ratio IS NOT NULL
+         UNION ALL
+         SELECT captured_at, interval_secs, changed_ratio, changed_regions, 1, 1, displays, captured_ts, id
+         FROM activity_samples
+         WHERE captured_ts >= ?1 AND captured_ts < ?2
+         ORDER BY 8, 9"
+    ).expect("Failed to prepare query");
+
+    let intervals: Vec<ActivityInterval> = stmt
+        .query_map([from_ts, to_ts], |row| {
+            let displays: String = row.get(6)?;
+            Ok(ActivityInterval {
+                captured_at: row.get(0)?,
+                interval_secs: row.get(1)?,
+                changed_ratio: row.get(2)?,
+                changed_regions: row.get(3)?,
+                afk: row.get(4)?,
+                sampled: row.get(5)?,
+                displays: serde_json::from_str(&displays).unwrap_or(Value::Null),
+            })
+        })
+        .map(|rows| rows.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+
+    serde_json::to_string(&intervals).unwrap_or_else(|_| "[]".to_string())
+}
@@ -0,0 +1,342 @@
+use image::{imageops::{self, FilterType}, Rgba, RgbaImage};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use super::visible_apps::WindowGeometry;
+
+/// Blurred regions are shrunk by this factor and scaled back up, which leaves text unreadable
+/// and is much cheaper than a Gaussian blur of the same strength.
+const BLUR_DOWNSCALE: u32 = 24;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MaskStyle {
+    Blur,
+    Blackout,
+}
+
+/// A window is masked when every field set in a rule matches.
+#[derive(Deserialize, Clone)]
+pub struct MaskRule {
+    /// Executable name, compared case-insensitively (e.g. `KeePass.exe`).
+    process: Option<String>,
+    /// Regular expression matched case-insensitively against the window title.
+    title: Option<String>,
+}
+
+impl MaskRule {
+    fn new(process: Option<&str>, title: Option<&str>) -> Self {
+        Self { process: process.map(str::to_string), title: title.map(str::to_string) }
+    }
+}
+
+/// The `masking` section of `screenshot_config.json`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MaskingConfig {
+    pub(crate) enabled: bool,
+    pub(crate) style: MaskStyle,
+    rules: Vec<MaskRule>,
+}
+
+impl Default for MaskingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            style: MaskStyle::Blur,
+            rules: vec![
+                MaskRule::new(Some("KeePass.exe"), None),
+                MaskRule::new(Some("KeePassXC.exe"), None),
+                MaskRule::new(Some("1Password.exe"), None),
+                MaskRule::new(Some("Bitwarden.exe"), None),
+                MaskRule::new(None, Some(r"\b(net ?banking|online banking|internet banking)\b")),
+                MaskRule::new(None, Some(r"\b(payslip|salary slip|password manager)\b")),
+            ],
+        }
+    }
+}
+
+/// A rectangle in desktop or image pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// Returns the overlap of two regions, if any.
+    pub(crate) fn intersect(&self, other: &Region) -> Option<Region> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width as i32).min(other.x + other.width as i32);
+        let bottom = (self.y + self.height as i32).min(other.y + other.height as i32);
+        (right > left && bottom > top).then(|| Region {
+            x: left,
+            y: top,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        })
+    }
+}
+
+impl MaskingConfig {
+    /// Returns the desktop bounds of every window matching a masking rule.
+    pub fn sensitive_regions(&self, windows: &[WindowGeometry]) -> Vec<Region> {
+        // Rules with an invalid title pattern are skipped rather than masking everything.
+        let rules: Vec<(Option<&str>, Option<Regex>)> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                let title = match &rule.title {
+                    Some(pattern) => match RegexBuilder::new(pattern).case_insensitive(true).build() {
+                        Ok(regex) => Some(regex),
+                        Err(err) => {
+                            eprintln!("Ignoring invalid masking pattern {}: {}", pattern, err);
+                            return None;
+                        }
+                    },
+                    None => None,
+                };
+                Some((rule.process.as_deref(), title))
+            })
+            .filter(|(process, title)| process.is_some() || title.is_some())
+            .collect();
+
+        windows
+            .iter()
+            .filter(|window| {
+                rules.iter().any(|(process, title)| {
+                    process.is_none_or(|process| process.eq_ignore_ascii_case(&window.process_name))
+                        && title.as_ref().is_none_or(|title| title.is_match(&window.title))
+                })
+            })
+            .map(|window| Region { x: window.x, y: window.y, width: window.width, height: window.height })
+            .collect()
+    }
+}
+
+/// Blurs or blacks out `regions` (in image pixels) of the image. Regions are clipped to the image.
+pub fn mask_regions(image: &mut RgbaImage, regions: &[Region], style: MaskStyle) {
+    let bounds = Region { x: 0, y: 0, width: image.width(), height: image.height() };
+
+    for region in regions.iter().filter_map(|region| region.intersect(&bounds)) {
+        let (x, y) = (region.x as u32, region.y as u32);
+        match style {
+            MaskStyle::Blackout => {
+                for py in y..y + region.height {
+                    for px in x..x + region.width {
+                        image.put_pixel(px, py, Rgba([0, 0, 0, 255]));
+                    }
+                }
+            }
+            MaskStyle::Blur => {
+                let area = imageops::crop_imm(image, x, y, region.width, region.height).to_image();
+                let small = imageops::resize(
+                    &area,
+                    (region.width / BLUR_DOWNSCALE).max(1),
+                    (region.height / BLUR_DOWNSCALE).max(1),
+                    FilterType::Triangle,
+                );
+                let blurred = imageops::resize(&small, region.width, region.height, FilterType::Triangle);
+                imageops::replace(image, &blurred, x as i64, y as i64);
+            }
+        }
+    }
+}
+
+/// Masks the parts of `regions` (in desktop pixels) that fall on one display's capture.
+/// `display` is the display's bounds in desktop pixels; the capture may be at a different scale.
+pub fn mask_display(image: &mut RgbaImage, display: Region, regions: &[Region], style: MaskStyle) {
+    let scale_x = image.width() as f64 / display.width.max(1) as f64;
+    let scale_y = image.height() as f64 / display.height.max(1) as f64;
+
+    let on_display: Vec<Region> = regions
+        .iter()
+        .filter_map(|region| region.intersect(&display))
+        .map(|region| {
+            // Round outwards so that no edge of the window is left unmasked.
+            let left = ((region.x - display.x) as f64 * scale_x).floor() as i32;
+            let top = ((region.y - display.y) as f64 * scale_y).floor() as i32;
+            let right = ((region.x - display.x + region.width as i32) as f64 * scale_x).ceil() as i32;
+            let bottom = ((region.y - display.y + region.height as i32) as f64 * scale_y).ceil() as i32;
+            Region { x: left, y: top, width: (right - left) as u32, height: (bottom - top) as u32 }
+        })
+        .collect();
+
+    mask_regions(image, &on_display, style);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    fn region(x: i32, y: i32, width: u32, height: u32) -> Region {
+        Region { x, y, width, height }
+    }
+
+    /// One-pixel black and white checkerboard: any blur turns it grey.
+    fn checkerboard(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| if (x + y) % 2 == 0 { WHITE } else { BLACK })
+    }
+
+    fn window(process: &str, title: &str) -> WindowGeometry {
+        WindowGeometry {
+            title: title.to_string(),
+            pid: 1,
+            process_name: process.to_string(),
+            x: 10,
+            y: 20,
+            width: 300,
+            height: 200,
+        }
+    }
+
+    /// The pixels of `image` that differ from `original`.
+    fn changed_pixels(original: &RgbaImage, image: &RgbaImage) -> Vec<(u32, u32)> {
+        image
+            .enumerate_pixels()
+            .filter(|(x, y, pixel)| original.get_pixel(*x, *y) != *pixel)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    /// Asserts that exactly the pixels inside `expected` (in image pixels) were blacked out.
+    fn assert_blacked_out(image: &RgbaImage, expected: Region) {
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let inside = expected.intersect(&region(x as i32, y as i32, 1, 1)).is_some();
+            assert_eq!(*pixel == BLACK, inside, "pixel ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn blackout_covers_the_region() {
+        let mut image = RgbaImage::from_pixel(10, 10, WHITE);
+        mask_regions(&mut image, &[region(2, 3, 4, 5)], MaskStyle::Blackout);
+        assert_blacked_out(&image, region(2, 3, 4, 5));
+    }
+
+    #[test]
+    fn blackout_clips_to_the_image() {
+        let mut image = RgbaImage::from_pixel(10, 10, WHITE);
+        let regions = [region(-5, -5, 8, 8), region(8, 8, 10, 10), region(20, 20, 5, 5)];
+        mask_regions(&mut image, &regions, MaskStyle::Blackout);
+
+        let black: Vec<(u32, u32)> = changed_pixels(&RgbaImage::from_pixel(10, 10, WHITE), &image);
+        assert_eq!(black.len(), 3 * 3 + 2 * 2);
+        assert!(black.iter().all(|&(x, y)| (x < 3 && y < 3) || (x >= 8 && y >= 8)));
+    }
+
+    #[test]
+    fn blur_only_changes_the_region() {
+        let original = checkerboard(64, 64);
+        let mut image = original.clone();
+        mask_regions(&mut image, &[region(16, 16, 32, 32)], MaskStyle::Blur);
+
+        let changed = changed_pixels(&original, &image);
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|&(x, y)| (16..48).contains(&x) && (16..48).contains(&y)));
+        // Nothing of the pattern is left: every pixel inside is mid-grey
+        for y in 16..48 {
+            for x in 16..48 {
+                assert!((64..=192).contains(&image.get_pixel(x, y)[0]), "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn blur_clips_to_the_image() {
+        let original = checkerboard(64, 64);
+        let mut image = original.clone();
+        mask_regions(&mut image, &[region(-10, 50, 30, 30), region(100, 0, 10, 10)], MaskStyle::Blur);
+
+        let changed = changed_pixels(&original, &image);
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|&(x, y)| x < 20 && y >= 50));
+    }
+
+    #[test]
+    fn display_offsets_are_applied() {
+        // Second display, right of a 1920 wide primary; the window straddles both
+        let display = region(1920, 0, 1920, 1080);
+        let mut image = RgbaImage::from_pixel(1920, 1080, WHITE);
+        mask_display(&mut image, display, &[region(1900, 100, 120, 50)], MaskStyle::Blackout);
+        assert_blacked_out(&image, region(0, 100, 100, 50));
+    }
+
+    #[test]
+    fn display_scale_is_applied() {
+        // A display left of the primary, captured at twice its desktop resolution
+        let display = region(-100, 0, 100, 50);
+        let mut image = RgbaImage::from_pixel(200, 100, WHITE);
+        mask_display(&mut image, display, &[region(-90, 10, 20, 5)], MaskStyle::Blackout);
+        assert_blacked_out(&image, region(20, 20, 40, 10));
+    }
+
+    #[test]
+    fn fractional_edges_round_outwards() {
+        // At 1.5x, desktop x 1..3 maps to 1.5..4.5 and y 1..2 to 1.5..3.0
+        let display = region(0, 0, 100, 100);
+        let mut image = RgbaImage::from_pixel(150, 150, WHITE);
+        mask_display(&mut image, display, &[region(1, 1, 2, 1)], MaskStyle::Blackout);
+        assert_blacked_out(&image, region(1, 1, 4, 2));
+    }
+
+    #[test]
+    fn windows_off_the_display_are_ignored() {
+        let mut image = RgbaImage::from_pixel(100, 100, WHITE);
+        mask_display(&mut image, region(0, 0, 100, 100), &[region(-50, 0, 50, 50)], MaskStyle::Blackout);
+        assert!(changed_pixels(&RgbaImage::from_pixel(100, 100, WHITE), &image).is_empty());
+    }
+
+    #[test]
+    fn intersect_handles_negative_coordinates() {
+        assert_eq!(region(-10, -10, 20, 20).intersect(&region(-5, 0, 30, 5)), Some(region(-5, 0, 15, 5)));
+        assert_eq!(region(-10, -10, 5, 5).intersect(&region(-7, -8, 10, 10)), Some(region(-7, -8, 2, 3)));
+        assert_eq!(region(-1920, -200, 1920, 1080).intersect(&region(0, 0, 1920, 1080)), None);
+        // Touching edges do not overlap
+        assert_eq!(region(-10, -10, 5, 5).intersect(&region(-5, -5, 5, 5)), None);
+    }
+
+    #[test]
+    fn rules_match_process_title_or_both() {
+        let config = MaskingConfig {
+            enabled: true,
+            style: MaskStyle::Blackout,
+            rules: vec![
+                MaskRule::new(Some("KeePass.exe"), None),
+                MaskRule::new(None, Some(r"\bbank\b")),
+                MaskRule::new(Some("chrome.exe"), Some("payslip")),
+                MaskRule::new(None, None),
+            ],
+        };
+        let windows = [
+            window("keepass.exe", "Database"),
+            window("firefox.exe", "My BANK - Login"),
+            window("chrome.exe", "Payslip March"),
+            window("chrome.exe", "News"),
+            window("msedge.exe", "payslip"),
+            window("notepad.exe", "embankment"),
+        ];
+
+        let masked = config.sensitive_regions(&windows);
+        assert_eq!(masked.len(), 3);
+        assert!(masked.iter().all(|masked| *masked == region(10, 20, 300, 200)));
+    }
+
+    #[test]
+    fn invalid_title_patterns_are_skipped() {
+        let config = MaskingConfig {
+            enabled: true,
+            style: MaskStyle::Blur,
+            rules: vec![MaskRule::new(Some("notepad.exe"), Some("(unclosed")), MaskRule::new(None, Some("secret"))],
+        };
+        let windows = [window("notepad.exe", "(unclosed"), window("notepad.exe", "secret.txt")];
+
+        let masked = config.sensitive_regions(&windows);
+        assert_eq!(masked.len(), 1);
+    }
+}
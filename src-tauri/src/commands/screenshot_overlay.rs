@@ -0,0 +1,91 @@
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use std::fs;
+
+/// Fonts tried in order for burned-in text; the first one that loads is used.
+const FONT_PATHS: &[&str] = &[
+    "C:\\Windows\\Fonts\\consola.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+    "C:\\Windows\\Fonts\\segoeui.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+];
+
+static OVERLAY_FONT: Lazy<Option<FontVec>> = Lazy::new(|| {
+    let font = FONT_PATHS
+        .iter()
+        .find_map(|path| fs::read(path).ok().and_then(|bytes| FontVec::try_from_vec(bytes).ok()));
+    if font.is_none() {
+        eprintln!("No overlay font found; text overlays are skipped");
+    }
+    font
+});
+
+/// Where the label box is placed.
+#[derive(Clone, Copy)]
+pub enum Corner {
+    TopLeft,
+    BottomRight,
+}
+
+fn blend(pixel: &mut Rgba<u8>, color: [u8; 3], alpha: f32) {
+    for channel in 0..3 {
+        let value = pixel[channel] as f32 * (1.0 - alpha) + color[channel] as f32 * alpha;
+        pixel[channel] = value.round() as u8;
+    }
+}
+
+/// Draws one or more lines of white text on a translucent black box in the given corner.
+/// The text height scales with the image so it stays readable after resizing.
+/// Nothing is drawn when no font is available.
+pub fn draw_label(image: &mut RgbaImage, lines: &[String], corner: Corner) {
+    let Some(font) = OVERLAY_FONT.as_ref() else {
+        return;
+    };
+
+    let size = (image.height() as f32 / 40.0).clamp(12.0, 48.0);
+    let scaled = font.as_scaled(PxScale::from(size));
+    let line_height = scaled.height() + scaled.line_gap();
+    let padding = (size / 3.0).ceil();
+
+    let text_width = lines
+        .iter()
+        .map(|line| line.chars().map(|c| scaled.h_advance(font.glyph_id(c))).sum::<f32>())
+        .fold(0.0, f32::max);
+    let box_width = (text_width + padding * 2.0).ceil() as u32;
+    let box_height = (line_height * lines.len() as f32 + padding * 2.0).ceil() as u32;
+    let box_width = box_width.min(image.width());
+    let box_height = box_height.min(image.height());
+    let (box_x, box_y) = match corner {
+        Corner::TopLeft => (0, 0),
+        Corner::BottomRight => (image.width() - box_width, image.height() - box_height),
+    };
+
+    for y in box_y..box_y + box_height {
+        for x in box_x..box_x + box_width {
+            blend(image.get_pixel_mut(x, y), [0, 0, 0], 0.6);
+        }
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let mut caret = box_x as f32 + padding;
+        let baseline = box_y as f32 + padding + scaled.ascent() + line_height * index as f32;
+
+        for c in line.chars() {
+            let mut glyph = scaled.scaled_glyph(c);
+            glyph.position = point(caret, baseline);
+            caret += scaled.h_advance(glyph.id);
+
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let x = bounds.min.x as i64 + gx as i64;
+                    let y = bounds.min.y as i64 + gy as i64;
+                    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+                        blend(image.get_pixel_mut(x as u32, y as u32), [255, 255, 255], coverage.min(1.0));
+                    }
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Weekday};
+use cron::Schedule;
+use rand::Rng;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Daily time window in local time, `HH:MM`. A window whose end is before its start spans midnight.
+#[derive(Deserialize)]
+pub struct WorkingHours {
+    start: String,
+    end: String,
+}
+
+/// The `schedule` section of `screenshot_config.json`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Cron expressions in local time, with a seconds field: `sec min hour day month weekday`.
+    /// Read once at startup.
+    pub(crate) cron: Vec<String>,
+    /// Each run waits a random 0..=`jitter_secs` seconds before capturing, so captures cannot be
+    /// predicted from the clock. The wait always ends before the schedule's next run, so a value
+    /// larger than the interval only spreads captures across the whole interval.
+    jitter_secs: u64,
+    /// Captures only run inside this window; `None` means all day.
+    working_hours: Option<WorkingHours>,
+    /// Days on which captures run, e.g. `["Mon", "Tue"]`.
+    working_days: Vec<String>,
+    /// Dates (`YYYY-MM-DD`) on which no captures run.
+    holidays: Vec<String>,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            cron: vec!["0 */10 * * * *".to_string()], // every 10 minutes
+            jitter_secs: 120,
+            working_hours: None,
+            working_days: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].iter().map(|d| d.to_string()).collect(),
+            holidays: Vec::new(),
+        }
+    }
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M").ok()
+}
+
+impl ScheduleConfig {
+    /// Random delay for a run of the cron `expression`, ending before its next run.
+    pub fn jitter(&self, expression: &str) -> Duration {
+        let now = Local::now();
+        let until_next_run = Schedule::from_str(expression)
+            .ok()
+            .and_then(|schedule| schedule.after(&now).next())
+            .map(|next| (next - now).num_seconds().max(1) as u64 - 1)
+            .unwrap_or(0);
+        let max_secs = self.jitter_secs.min(until_next_run);
+        if max_secs == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs(rand::thread_rng().gen_range(0..=max_secs))
+    }
+
+    /// Returns whether a capture may run at `at`: on a working day that is not a holiday,
+    /// and inside working hours. Unparsable entries are ignored.
+    pub fn allows(&self, at: DateTime<Local>) -> bool {
+        let today = at.date_naive();
+        if self
+            .holidays
+            .iter()
+            .filter_map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+            .any(|holiday| holiday == today)
+        {
+            return false;
+        }
+
+        let weekday = at.weekday();
+        if !self.working_days.iter().filter_map(|day| day.parse::<Weekday>().ok()).any(|day| day == weekday) {
+            return false;
+        }
+
+        match &self.working_hours {
+            Some(hours) => match (parse_time(&hours.start), parse_time(&hours.end)) {
+                (Some(start), Some(end)) => {
+                    let now = at.time();
+                    if start <= end {
+                        now >= start && now < end
+                    } else {
+                        now >= start || now < end
+                    }
+                }
+                _ => {
+                    eprintln!("Ignoring invalid working hours {}-{}", hours.start, hours.end);
+                    true
+                }
+            },
+            None => true,
+        }
+    }
+}
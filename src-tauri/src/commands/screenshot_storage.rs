@@ -0,0 +1,213 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use sysinfo::Disks;
+use tokio::task;
+use super::capture_screen::{load_screenshot_config, SCREENSHOT_DIR};
+use super::screenshot_evidence::signature_path;
+use super::screenshot_index::{delete_oldest_capture, delete_screenshots_before, index_summary};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The `storage` section of `screenshot_config.json`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Upper bound for everything under `SCREENSHOT_DIR`; `None` means unbounded.
+    max_bytes: Option<u64>,
+    /// Captures (and timelapses) older than this are deleted; `None` keeps them forever.
+    max_age_days: Option<u32>,
+    /// Capturing pauses while the screenshot disk has less free space than this.
+    min_free_bytes: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(10 * 1024 * BYTES_PER_MB), // 10 GB
+            max_age_days: Some(90),
+            min_free_bytes: 2 * 1024 * BYTES_PER_MB, // 2 GB
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Returns the free space when it is below `min_free_bytes`, i.e. when capturing should pause.
+    pub fn low_disk_space(&self) -> Option<u64> {
+        free_space(Path::new(SCREENSHOT_DIR)).filter(|free| *free < self.min_free_bytes)
+    }
+}
+
+#[derive(Serialize)]
+pub struct StorageStats {
+    directory: &'static str,
+    total_bytes: u64,
+    file_count: usize,
+    screenshot_count: i64,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`.
+    oldest_captured_at: Option<String>,
+    newest_captured_at: Option<String>,
+    max_bytes: Option<u64>,
+    max_age_days: Option<u32>,
+    /// `None` when the disk could not be determined.
+    free_bytes: Option<u64>,
+    min_free_bytes: u64,
+    capture_paused: bool,
+}
+
+/// Free space on the disk holding `path`: the disk with the longest matching mount point.
+fn free_space(path: &Path) -> Option<u64> {
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// Total size and number of files under `dir`, including thumbnails and timelapses.
+fn directory_usage(dir: &Path) -> (u64, usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    entries.flatten().fold((0, 0), |(bytes, files), entry| match entry.metadata() {
+        Ok(metadata) if metadata.is_dir() => {
+            let (dir_bytes, dir_files) = directory_usage(&entry.path());
+            (bytes + dir_bytes, files + dir_files)
+        }
+        Ok(metadata) => (bytes + metadata.len(), files + 1),
+        Err(_) => (bytes, files),
+    })
+}
+
+/// Every file under `dir`, oldest modification first.
+fn files_oldest_first(dir: &Path) -> Vec<PathBuf> {
+    fn collect(dir: &Path, files: &mut Vec<(SystemTime, PathBuf)>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => collect(&entry.path(), files),
+                Ok(metadata) => files.push((metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), entry.path())),
+                Err(_) => {}
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    collect(dir, &mut files);
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Deletes the files, with their signatures, and returns how many bytes were freed.
+/// Files already gone are skipped.
+fn remove_files(paths: &[String]) -> u64 {
+    paths
+        .iter()
+        .flat_map(|path| [path.clone(), signature_path(Path::new(path)).to_string_lossy().to_string()])
+        .map(|path| {
+            let size = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+            match fs::remove_file(&path) {
+                Ok(()) => size,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+                Err(e) => {
+                    eprintln!("Failed to delete {}: {}", path, e);
+                    0
+                }
+            }
+        })
+        .sum()
+}
+
+/// Deletes timelapses last modified before `cutoff` (Unix seconds).
+fn remove_old_timelapses(cutoff: i64) -> usize {
+    let Ok(entries) = fs::read_dir(Path::new(SCREENSHOT_DIR).join("timelapse")) else {
+        return 0;
+    };
+    let old: Vec<String> = entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| chrono::DateTime::<Utc>::from(modified).timestamp() < cutoff)
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.path().to_string_lossy().to_string())
+        .collect();
+    remove_files(&old);
+    old.len()
+}
+
+/// Applies the quota after a capture: first drops everything older than `max_age_days`,
+/// then deletes whole captures, oldest first, until the directory fits in `max_bytes`.
+/// Once no indexed capture is left, timelapses and unindexed files are deleted oldest first,
+/// since they count towards `max_bytes` too. Returns the number of index rows removed.
+pub fn enforce_quota(config: &StorageConfig) -> Result<usize, String> {
+    let mut removed = 0;
+
+    if let Some(days) = config.max_age_days {
+        let cutoff = Utc::now().timestamp() - days as i64 * SECONDS_PER_DAY;
+        let (rows, files) = delete_screenshots_before(cutoff)?;
+        remove_files(&files);
+        remove_old_timelapses(cutoff);
+        removed += rows;
+    }
+
+    if let Some(max_bytes) = config.max_bytes {
+        let (mut used, _) = directory_usage(Path::new(SCREENSHOT_DIR));
+        while used > max_bytes {
+            let (rows, files) = delete_oldest_capture()?;
+            if rows == 0 {
+                for path in files_oldest_first(Path::new(SCREENSHOT_DIR)) {
+                    if used <= max_bytes {
+                        break;
+                    }
+                    used = used.saturating_sub(remove_files(&[path.to_string_lossy().to_string()]));
+                }
+                break;
+            }
+            used = used.saturating_sub(remove_files(&files));
+            removed += rows;
+        }
+    }
+
+    if removed > 0 {
+        println!("Screenshot quota: removed {} old screenshots", removed);
+    }
+    Ok(removed)
+}
+
+/// Returns disk usage of the screenshot directory against the configured quota.
+#[tauri::command]
+pub async fn get_screenshot_storage_stats() -> Result<String, String> {
+    let stats = task::spawn_blocking(|| {
+        let storage = load_screenshot_config().storage;
+        let (total_bytes, file_count) = directory_usage(Path::new(SCREENSHOT_DIR));
+        let (screenshot_count, oldest_captured_at, newest_captured_at) = index_summary();
+        let free_bytes = free_space(Path::new(SCREENSHOT_DIR));
+
+        StorageStats {
+            directory: SCREENSHOT_DIR,
+            total_bytes,
+            file_count,
+            screenshot_count,
+            oldest_captured_at,
+            newest_captured_at,
+            max_bytes: storage.max_bytes,
+            max_age_days: storage.max_age_days,
+            free_bytes,
+            min_free_bytes: storage.min_free_bytes,
+            capture_paused: free_bytes.is_some_and(|free| free < storage.min_free_bytes),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    serde_json::to_string(&stats).map_err(|e| e.to_string())
+}
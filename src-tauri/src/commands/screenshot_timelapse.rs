@@ -0,0 +1,287 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Local, NaiveDate, TimeZone};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{imageops::FilterType, DynamicImage, Delay, Frame, ImageOutputFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, create_dir_all};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tokio::task;
+use crate::utils::time::local_date_range;
+use super::capture_screen::{load_screenshot_config, read_screenshot_file, SCREENSHOT_DIR};
+use super::screenshot_crypto::{encrypt, encrypted_path};
+use super::screenshot_index::screenshots_between;
+use super::screenshot_overlay::{draw_label, Corner};
+
+/// GIF quantizer speed (1 = best colours, 30 = fastest); 10 keeps a day's timelapse to seconds.
+const GIF_SPEED: i32 = 10;
+const MJPEG_QUALITY: u8 = 80;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelapseFormat {
+    Gif,
+    /// Motion JPEG in an AVI container.
+    Mjpeg,
+}
+
+/// The `timelapse` section of `screenshot_config.json`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TimelapseConfig {
+    format: TimelapseFormat,
+    fps: u32,
+    /// Frames are scaled down to at most this width.
+    max_width: u32,
+    /// Cron expression (local time) for generating the day's timelapse; `None` disables it.
+    pub(crate) end_of_day: Option<String>,
+}
+
+impl Default for TimelapseConfig {
+    fn default() -> Self {
+        Self {
+            format: TimelapseFormat::Gif,
+            fps: 4,
+            max_width: 960,
+            end_of_day: Some("0 55 23 * * *".to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TimelapseResult {
+    date: String,
+    path: String,
+    format: &'static str,
+    frames: usize,
+    bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct TimelapseVideo {
+    date: String,
+    format: &'static str,
+    /// The decrypted timelapse as a `data:` URL. GIFs play in an `<img>` element; browsers
+    /// cannot play MJPEG AVIs, so those are only good for downloading.
+    data_url: String,
+}
+
+/// Writes JPEG frames as an uncompressed-index AVI with the `MJPG` codec.
+fn write_mjpeg_avi(frames: &[Vec<u8>], width: u32, height: u32, fps: u32) -> Vec<u8> {
+    fn chunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+        out.extend_from_slice(id);
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+    fn list(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+        let mut data = kind.to_vec();
+        data.extend_from_slice(body);
+        chunk(out, b"LIST", &data);
+    }
+    let u32s = |values: &[u32]| values.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+
+    let frame_count = frames.len() as u32;
+    let largest = frames.iter().map(Vec::len).max().unwrap_or(0) as u32;
+
+    let avih = u32s(&[1_000_000 / fps, largest * fps, 0, 0x10, frame_count, 0, 1, largest, width, height, 0, 0, 0, 0]);
+    let mut strh = b"vidsMJPG".to_vec();
+    strh.extend(u32s(&[0, 0, 0, 1, fps, 0, frame_count, largest, u32::MAX, 0]));
+    strh.extend([0u16, 0, width as u16, height as u16].iter().flat_map(|v| v.to_le_bytes()));
+    let mut strf = u32s(&[40, width, height]);
+    strf.extend([1u16, 24].iter().flat_map(|v| v.to_le_bytes()));
+    strf.extend_from_slice(b"MJPG");
+    strf.extend(u32s(&[width * height * 3, 0, 0, 0, 0]));
+
+    let mut strl = Vec::new();
+    chunk(&mut strl, b"strh", &strh);
+    chunk(&mut strl, b"strf", &strf);
+    let mut hdrl = Vec::new();
+    chunk(&mut hdrl, b"avih", &avih);
+    list(&mut hdrl, b"strl", &strl);
+
+    // Index offsets are relative to the `movi` list type.
+    let mut movi = Vec::new();
+    let mut idx1 = Vec::new();
+    for frame in frames {
+        idx1.extend_from_slice(b"00dc");
+        idx1.extend(u32s(&[0x10, movi.len() as u32 + 4, frame.len() as u32]));
+        chunk(&mut movi, b"00dc", frame);
+    }
+
+    let mut body = b"AVI ".to_vec();
+    list(&mut body, b"hdrl", &hdrl);
+    list(&mut body, b"movi", &movi);
+    chunk(&mut body, b"idx1", &idx1);
+
+    let mut avi = Vec::new();
+    chunk(&mut avi, b"RIFF", &body);
+    avi
+}
+
+/// Builds the timelapse of one local day from the indexed screenshots, with each frame's
+/// capture time burned in. When several files share a capture (per-display layout) only
+/// the first is used. The file is encrypted like the screenshots themselves.
+pub fn generate_timelapse_for(date: NaiveDate) -> Result<TimelapseResult, String> {
+    let config = load_screenshot_config();
+    let timelapse = &config.timelapse;
+    let fps = timelapse.fps.clamp(1, 60);
+
+    let day = date.format("%Y-%m-%d").to_string();
+    let (from_ts, to_ts) = local_date_range(Some(day.clone()), Some(day.clone()));
+    let screenshots = screenshots_between(from_ts, to_ts);
+    if screenshots.is_empty() {
+        return Err(format!("No screenshots on {}", day));
+    }
+
+    let mut size: Option<(u32, u32)> = None;
+    let mut last_decoded: Option<(String, RgbaImage)> = None;
+    let mut frames = 0;
+    // GIF frames are encoded as they are read; MJPEG frames are kept until the AVI index is known
+    let mut gif_data = Vec::new();
+    let mut gif_encoder = match timelapse.format {
+        TimelapseFormat::Gif => {
+            let mut encoder = GifEncoder::new_with_speed(&mut gif_data, GIF_SPEED);
+            encoder.set_repeat(Repeat::Infinite).map_err(|e| e.to_string())?;
+            Some(encoder)
+        }
+        TimelapseFormat::Mjpeg => None,
+    };
+    let mut jpeg_frames = Vec::new();
+
+    for (path, captured_ts) in screenshots {
+        let image = match &last_decoded {
+            // Deduplicated captures reference the same file
+            Some((last_path, image)) if *last_path == path => image.clone(),
+            _ => {
+                let decoded = read_screenshot_file(Path::new(&path))
+                    .and_then(|bytes| image::load_from_memory(&bytes).map_err(|e| e.to_string()));
+                match decoded {
+                    Ok(image) => {
+                        let image = image.to_rgba8();
+                        last_decoded = Some((path.clone(), image.clone()));
+                        image
+                    }
+                    Err(e) => {
+                        eprintln!("Skipping timelapse frame {}: {}", path, e);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let (width, height) = *size.get_or_insert_with(|| {
+            let width = image.width().min(timelapse.max_width.max(1));
+            let height = (image.height() as u64 * width as u64 / image.width().max(1) as u64).max(1) as u32;
+            (width, height)
+        });
+        let mut frame = if image.dimensions() == (width, height) {
+            image
+        } else {
+            image::imageops::resize(&image, width, height, FilterType::Triangle)
+        };
+
+        let captured_at = Local
+            .timestamp_opt(captured_ts, 0)
+            .single()
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        draw_label(&mut frame, &[captured_at], Corner::TopLeft);
+
+        match gif_encoder.as_mut() {
+            Some(encoder) => encoder
+                .encode_frame(Frame::from_parts(frame, 0, 0, Delay::from_numer_denom_ms(1000, fps)))
+                .map_err(|e| e.to_string())?,
+            None => {
+                let mut jpeg = Vec::new();
+                DynamicImage::ImageRgba8(frame)
+                    .to_rgb8()
+                    .write_to(&mut Cursor::new(&mut jpeg), ImageOutputFormat::Jpeg(MJPEG_QUALITY))
+                    .map_err(|e| e.to_string())?;
+                jpeg_frames.push(jpeg);
+            }
+        }
+        frames += 1;
+    }
+    drop(gif_encoder);
+
+    let (width, height) = size.ok_or_else(|| format!("No readable screenshots on {}", day))?;
+    let (extension, data) = match timelapse.format {
+        TimelapseFormat::Gif => ("gif", gif_data),
+        TimelapseFormat::Mjpeg => ("avi", write_mjpeg_avi(&jpeg_frames, width, height, fps)),
+    };
+
+    let dir = Path::new(SCREENSHOT_DIR).join("timelapse");
+    create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut path: PathBuf = dir.join(format!("timelapse-{}.{}", day, extension));
+    let data = if config.encryption.enabled {
+        path = encrypted_path(&path);
+        encrypt(&config.encryption.cipher()?, &data)?
+    } else {
+        data
+    };
+    fs::write(&path, &data).map_err(|e| e.to_string())?;
+
+    Ok(TimelapseResult {
+        date: day,
+        path: path.to_string_lossy().to_string(),
+        format: extension,
+        frames,
+        bytes: data.len() as u64,
+    })
+}
+
+/// Finds the timelapse of `day`, encrypted or not; the newest if it was generated in more than one format.
+fn timelapse_file(day: &str) -> Option<(PathBuf, &'static str)> {
+    let dir = Path::new(SCREENSHOT_DIR).join("timelapse");
+    ["gif", "avi"]
+        .into_iter()
+        .flat_map(|extension| {
+            let path = dir.join(format!("timelapse-{}.{}", day, extension));
+            [(encrypted_path(&path), extension), (path, extension)]
+        })
+        .filter_map(|(path, extension)| {
+            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+            Some((modified, path, extension))
+        })
+        .max_by_key(|(modified, _, _)| *modified)
+        .map(|(_, path, extension)| (path, extension))
+}
+
+fn parse_date(date: Option<String>) -> Result<NaiveDate, String> {
+    match date {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| e.to_string()),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+/// Generates the timelapse for `date` (`YYYY-MM-DD`, default today) and returns where it was saved.
+#[tauri::command]
+pub async fn generate_timelapse(date: Option<String>) -> Result<String, String> {
+    let date = parse_date(date)?;
+    let result = task::spawn_blocking(move || generate_timelapse_for(date))
+        .await
+        .map_err(|e| e.to_string())??;
+    serde_json::to_string(&result).map_err(|e| e.to_string())
+}
+
+/// Returns the generated timelapse for `date` (`YYYY-MM-DD`, default today), decrypted, for the viewer.
+#[tauri::command]
+pub async fn get_timelapse(date: Option<String>) -> Result<String, String> {
+    let day = parse_date(date)?.format("%Y-%m-%d").to_string();
+    let video = task::spawn_blocking(move || {
+        let (path, format) = timelapse_file(&day).ok_or_else(|| format!("No timelapse for {}", day))?;
+        let data = read_screenshot_file(&path)?;
+        let mime_type = if format == "gif" { "image/gif" } else { "video/x-msvideo" };
+        Ok::<_, String>(TimelapseVideo {
+            date: day,
+            format,
+            data_url: format!("data:{};base64,{}", mime_type, STANDARD.encode(data)),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    serde_json::to_string(&video).map_err(|e| e.to_string())
+}
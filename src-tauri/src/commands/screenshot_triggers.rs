@@ -0,0 +1,204 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessesToUpdate, System};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use super::afk_tracker::is_session_locked;
+use super::browser_categories::{categorize_url, split_url};
+use super::capture_screen::{capture_screens, load_screenshot_config};
+use super::usb_monitor::removable_drives;
+
+/// Removable drives and processes are polled this often.
+const POLL_SECS: u64 = 5;
+
+/// What caused a capture; stored with each screenshot.
+#[derive(Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureTrigger {
+    Schedule,
+    /// Requested from the UI through `get_capture_screen`.
+    Manual,
+    UsbStorage { drive: String },
+    ProcessStart { process: String },
+    DomainCategory { domain: String, category: String },
+    AfkReturn { away_secs: i64 },
+}
+
+impl CaptureTrigger {
+    fn kind(&self) -> &'static str {
+        match self {
+            CaptureTrigger::Schedule => "schedule",
+            CaptureTrigger::Manual => "manual",
+            CaptureTrigger::UsbStorage { .. } => "usb_storage",
+            CaptureTrigger::ProcessStart { .. } => "process_start",
+            CaptureTrigger::DomainCategory { .. } => "domain_category",
+            CaptureTrigger::AfkReturn { .. } => "afk_return",
+        }
+    }
+}
+
+/// The `triggers` section of `screenshot_config.json`: events that capture outside the schedule.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TriggerConfig {
+    /// Capture when a USB storage device is attached.
+    usb_storage: bool,
+    /// Capture when one of these executables starts, e.g. `"AnyDesk.exe"` (case-insensitive).
+    processes: Vec<String>,
+    /// Capture when the browser moves to a domain in one of these categories, e.g. `"social"`.
+    domain_categories: Vec<String>,
+    /// Capture when the user returns from being away.
+    afk_return: bool,
+    /// Minimum time between two captures of the same kind of trigger.
+    cooldown_secs: u64,
+}
+
+impl Default for TriggerConfig {
+    fn default() -> Self {
+        Self {
+            usb_storage: true,
+            processes: Vec::new(),
+            domain_categories: Vec::new(),
+            afk_return: true,
+            cooldown_secs: 60,
+        }
+    }
+}
+
+impl TriggerConfig {
+    fn is_enabled(&self, trigger: &CaptureTrigger) -> bool {
+        match trigger {
+            CaptureTrigger::Schedule | CaptureTrigger::Manual => true,
+            CaptureTrigger::UsbStorage { .. } => self.usb_storage,
+            CaptureTrigger::ProcessStart { process } => self.is_flagged_process(process),
+            CaptureTrigger::DomainCategory { category, .. } => {
+                self.domain_categories.iter().any(|flagged| flagged.eq_ignore_ascii_case(category))
+            }
+            CaptureTrigger::AfkReturn { .. } => self.afk_return,
+        }
+    }
+
+    fn is_flagged_process(&self, process: &str) -> bool {
+        self.processes.iter().any(|flagged| flagged.eq_ignore_ascii_case(process))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref TRIGGER_SENDER: Mutex<Option<UnboundedSender<CaptureTrigger>>> = Mutex::new(None);
+    /// Last capture per trigger kind, for the cooldown.
+    static ref LAST_TRIGGERED: Mutex<HashMap<&'static str, Instant>> = Mutex::new(HashMap::new());
+    static ref LAST_DOMAIN: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Requests a capture for an event. Safe to call from any thread; dropped until
+/// [`start_trigger_listener`] runs, and filtered by the config when handled.
+pub fn fire_trigger(trigger: CaptureTrigger) {
+    if let Some(sender) = TRIGGER_SENDER.lock().unwrap().as_ref() {
+        let _ = sender.send(trigger);
+    }
+}
+
+/// Called by the active-tab tracker with the URL in the foreground; fires once per domain change.
+pub fn on_site_visit(url: &str) {
+    let Some((domain, _)) = split_url(url) else {
+        return;
+    };
+    {
+        let mut last_domain = LAST_DOMAIN.lock().unwrap();
+        if last_domain.as_deref() == Some(domain.as_str()) {
+            return;
+        }
+        *last_domain = Some(domain.clone());
+    }
+    fire_trigger(CaptureTrigger::DomainCategory { domain, category: categorize_url(url) });
+}
+
+async fn run_triggered_capture(trigger: CaptureTrigger) {
+    let config = load_screenshot_config();
+    if !config.triggers.is_enabled(&trigger) {
+        return;
+    }
+    // Event captures follow the same working hours as scheduled ones, and are pointless while locked
+    if !config.schedule.allows(Local::now()) || is_session_locked() {
+        return;
+    }
+    {
+        let mut last_triggered = LAST_TRIGGERED.lock().unwrap();
+        let cooldown = Duration::from_secs(config.triggers.cooldown_secs);
+        if last_triggered.get(trigger.kind()).is_some_and(|at| at.elapsed() < cooldown) {
+            return;
+        }
+        last_triggered.insert(trigger.kind(), Instant::now());
+    }
+
+    let kind = trigger.kind();
+    match capture_screens(trigger).await {
+        Ok(saved) => println!("Screenshots triggered by {} saved: {}", kind, saved),
+        Err(e) => eprintln!("Failed to capture screenshot for {}: {}", kind, e),
+    }
+}
+
+/// Fires for entries of `current` that were not in `previous`; nothing on the first poll,
+/// so drives and processes present at startup do not trigger.
+fn fire_new(previous: &Option<HashSet<String>>, current: &HashSet<String>, trigger: impl Fn(String) -> CaptureTrigger) {
+    if let Some(previous) = previous {
+        for added in current.difference(previous) {
+            fire_trigger(trigger(added.clone()));
+        }
+    }
+}
+
+/// Polls for newly attached removable drives and newly started flagged processes.
+fn start_event_poller() {
+    thread::spawn(|| {
+        let mut system = System::new();
+        let mut drives: Option<HashSet<String>> = None;
+        let mut processes: Option<HashSet<String>> = None;
+
+        loop {
+            let config = load_screenshot_config().triggers;
+
+            drives = if config.usb_storage {
+                let current: HashSet<String> = removable_drives().into_iter().collect();
+                fire_new(&drives, &current, |drive| CaptureTrigger::UsbStorage { drive });
+                Some(current)
+            } else {
+                None
+            };
+
+            processes = if config.processes.is_empty() {
+                None
+            } else {
+                // Only the process list is needed; exited processes are dropped so a restart fires again
+                system.refresh_processes(ProcessesToUpdate::All, true);
+                let current: HashSet<String> = system
+                    .processes()
+                    .values()
+                    .map(|process| process.name().to_string_lossy().to_string())
+                    .filter(|name| config.is_flagged_process(name))
+                    .collect();
+                fire_new(&processes, &current, |process| CaptureTrigger::ProcessStart { process });
+                Some(current)
+            };
+
+            thread::sleep(Duration::from_secs(POLL_SECS));
+        }
+    });
+}
+
+/// Starts handling event triggers. Captures run one at a time, in the order the events arrived.
+/// Must be called from within the Tokio runtime.
+pub fn start_trigger_listener() {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    *TRIGGER_SENDER.lock().unwrap() = Some(sender);
+
+    tokio::spawn(async move {
+        while let Some(trigger) = receiver.recv().await {
+            run_triggered_capture(trigger).await;
+        }
+    });
+    start_event_poller();
+}
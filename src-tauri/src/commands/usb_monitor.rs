@@ -1,34 +1,24 @@
 use notify::{recommended_watcher, Event, RecursiveMode, Watcher};
 use std::sync::mpsc::{self, Receiver};
 use std::path::Path;
-use std::process::Command;
+use sysinfo::Disks;
 use tauri::command;
 use tokio::task;
 
+/// Returns the root paths of the removable drives that are currently mounted (e.g. `"E:\\"`).
+/// Card readers without a card are not listed.
+pub fn removable_drives() -> Vec<String> {
+    Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .filter(|disk| disk.is_removable())
+        .map(|disk| disk.mount_point().to_string_lossy().to_string())
+        .collect()
+}
+
 /// Function to get USB mount path
 fn get_mount_path() -> Option<String> {
-    let output = Command::new("wmic")
-        .args(&["logicaldisk", "where", "DriveType=2", "get", "DeviceID"])
-        .output()
-        .ok()?;
-
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    let drive_letters: Vec<String> = output_str
-        .lines()
-        .skip(1)
-        .filter_map(|line| {
-            let drive = line.trim();
-            if !drive.is_empty() {
-                Some(format!("{}\\", drive)) // Ensuring correct path format (e.g., "E:\")
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    drive_letters
-        .into_iter()
-        .find(|path| Path::new(path).exists())
+    removable_drives().into_iter().next()
 }
 
 /// Starts USB file monitoring (Runs asynchronously)
@@ -1,11 +1,14 @@
 use windows::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible, GetForegroundWindow
+    EnumWindows, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindowVisible, GetForegroundWindow
 };
-use windows::Win32::Foundation::{HWND, LPARAM, BOOL};
+use windows::Win32::Foundation::{HWND, LPARAM, BOOL, RECT};
 use std::collections::HashMap;
 use std::sync::Mutex;
-use chrono::{Utc, Duration};
+use chrono::Utc;
 use serde::{Serialize, Deserialize};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use crate::utils::time::format_duration;
+use super::private_browsing::{has_private_marker, mask_private_title};
 
 lazy_static::lazy_static! {
     static ref PROCESS_TIMES: Mutex<HashMap<String, (i64, i64, bool)>> = Mutex::new(HashMap::new());
@@ -22,15 +25,128 @@ pub struct VisibleApp {
     top_usage: String,
 }
 
+/// The window that currently has keyboard focus.
+pub struct ForegroundWindow {
+    pub title: String,
+    pub pid: u32,
+    pub process_name: String,
+}
+
+/// A visible, non-minimised top-level window and its bounds in desktop coordinates.
+pub struct WindowGeometry {
+    pub title: String,
+    pub pid: u32,
+    pub process_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Looks up the executable name (e.g. `chrome.exe`) of a process.
+pub fn get_process_name(pid: u32) -> String {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), false);
+    sys.process(pid)
+        .map(|process| process.name().to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+pub fn get_foreground_window() -> Option<ForegroundWindow> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+
+        let mut title = [0u16; 512];
+        let len = GetWindowTextW(hwnd, &mut title);
+        let mut pid = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+        Some(ForegroundWindow {
+            title: String::from_utf16_lossy(&title[..len.max(0) as usize]),
+            pid,
+            process_name: get_process_name(pid),
+        })
+    }
+}
+
+/// Returns the window's outer bounds as (x, y, width, height), or `None` for empty windows.
+fn window_rect(hwnd: HWND) -> Option<(i32, i32, u32, u32)> {
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(hwnd, &mut rect).ok()? };
+    let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+    (width > 0 && height > 0).then_some((rect.left, rect.top, width as u32, height as u32))
+}
+
+/// Returns the title, owner and bounds of a window, or `None` for empty windows.
+unsafe fn window_geometry(hwnd: HWND) -> Option<WindowGeometry> {
+    let (x, y, width, height) = window_rect(hwnd)?;
+    let mut title = [0u16; 512];
+    let len = GetWindowTextW(hwnd, &mut title);
+    let mut pid = 0;
+    GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+    Some(WindowGeometry {
+        title: String::from_utf16_lossy(&title[..len.max(0) as usize]),
+        pid,
+        process_name: get_process_name(pid),
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+unsafe extern "system" fn enum_geometry_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let windows = &mut *(lparam.0 as *mut Vec<WindowGeometry>);
+    if !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
+        return true.into();
+    }
+
+    if let Some(geometry) = window_geometry(hwnd) {
+        windows.push(geometry);
+    }
+
+    true.into()
+}
+
+/// Returns the bounds of the foreground window, or `None` when there is none or it is minimised.
+pub fn get_foreground_window_geometry() -> Option<WindowGeometry> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() || IsIconic(hwnd).as_bool() {
+            return None;
+        }
+        window_geometry(hwnd)
+    }
+}
+
+/// Lists visible top-level windows with their bounds, topmost first.
+pub fn get_window_geometries() -> Vec<WindowGeometry> {
+    let mut windows: Vec<WindowGeometry> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(enum_geometry_proc), LPARAM(&mut windows as *mut _ as isize));
+    }
+    windows
+}
+
 unsafe extern "system" fn enum_window_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
     let visible_apps = &mut *(lparam.0 as *mut Vec<VisibleApp>);
     let mut title = [0u16; 512];
     let len = GetWindowTextW(hwnd, &mut title);
 
     if IsWindowVisible(hwnd).as_bool() && len > 0 {
-        let window_title = String::from_utf16_lossy(&title[..len as usize]);
         let mut pid = 0;
         GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        let window_title = String::from_utf16_lossy(&title[..len as usize]);
+        let window_title = if has_private_marker(&window_title) {
+            mask_private_title(&get_process_name(pid), window_title)
+        } else {
+            window_title
+        };
 
         let now = Utc::now().timestamp();
         let mut process_times = PROCESS_TIMES.lock().unwrap();
@@ -120,8 +236,3 @@ pub fn get_visible_apps() -> String {
 
     serde_json::to_string(&visible_apps).unwrap_or_else(|_| "[]".to_string()) // Convert data to JSON format
 }
-
-fn format_duration(seconds: i64) -> String {
-    let duration = Duration::seconds(seconds);
-    format!("{:02}:{:02}:{:02}", duration.num_hours(), duration.num_minutes() % 60, duration.num_seconds() % 60)
-}
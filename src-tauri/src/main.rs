@@ -1,22 +1,36 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands;
+mod utils;
 use commands::{
     system::{get_ram_usage, track_ram_usage},
     installed_apps::get_installed_apps,
-    browser::get_browser_history,
+    browser::{get_browser_history, get_browser_profiles},
+    browser_categories::get_browsing_category_summary,
+    browser_extensions::get_browser_extensions,
+    browser_sessions::get_open_tabs,
+    browser_search_terms::{get_search_terms, get_top_searches},
     visible_apps::get_visible_apps,
     running_apps::get_running_apps,
     capture_screen::{get_capture_screen, start_screenshot_scheduler},
+    screenshot_evidence::{get_signing_public_key, verify_screenshot},
+    screenshot_index::{get_screen_activity, get_screenshot, get_screenshot_gallery, list_screenshots},
+    screenshot_storage::get_screenshot_storage_stats,
+    screenshot_timelapse::{generate_timelapse, get_timelapse},
     usb_devices::list_usb_devices,
     usb_monitor::monitor_usb_file_transfers,
     afk_tracker::{start_afk_tracker, get_afk_status},
+    active_tab::{start_active_tab_tracker, get_time_on_site},
+    private_browsing::get_private_browsing_time,
 };
 use tokio::runtime::Runtime;
+use utils::db::init_database;
 
 fn main() {
+    init_database();
     track_ram_usage();
     start_afk_tracker();    
+    start_active_tab_tracker();
 
     let runtime = Runtime::new().expect("Failed to create Tokio runtime");
 
@@ -32,11 +46,28 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             get_afk_status,
             get_visible_apps,
+            get_time_on_site,
+            get_private_browsing_time,
             get_running_apps,
             get_ram_usage,
             get_installed_apps,
             get_browser_history,
+            get_browser_profiles,
+            get_browsing_category_summary,
+            get_browser_extensions,
+            get_open_tabs,
+            get_search_terms,
+            get_top_searches,
             get_capture_screen,
+            list_screenshots,
+            get_screenshot,
+            get_screenshot_gallery,
+            get_screen_activity,
+            generate_timelapse,
+            get_timelapse,
+            get_screenshot_storage_stats,
+            verify_screenshot,
+            get_signing_public_key,
             list_usb_devices,
             monitor_usb_file_transfers,
         ])
@@ -0,0 +1,29 @@
+use rusqlite::Connection;
+use std::sync::{Mutex, MutexGuard};
+use crate::commands::{active_tab, browser_search_terms, private_browsing, screenshot_index};
+
+lazy_static::lazy_static! {
+    static ref DB_CONN: Mutex<Connection> = Mutex::new(open_database());
+}
+
+/// Opens `ems_data.db` in the working directory and creates or migrates the tables
+/// of every module that stores its data there.
+fn open_database() -> Connection {
+    let conn = Connection::open("ems_data.db").expect("Failed to open database");
+    active_tab::ensure_table(&conn);
+    private_browsing::ensure_table(&conn);
+    browser_search_terms::ensure_table(&conn);
+    screenshot_index::ensure_table(&conn);
+    conn
+}
+
+/// Opens the database and sets up its schema. Called once at startup, so queries can
+/// assume every table exists.
+pub fn init_database() {
+    drop(connection());
+}
+
+/// The connection to `ems_data.db` shared by the trackers.
+pub fn connection() -> MutexGuard<'static, Connection> {
+    DB_CONN.lock().unwrap()
+}
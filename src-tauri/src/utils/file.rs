@@ -0,0 +1,15 @@
+use serde::de::DeserializeOwned;
+use std::fs;
+
+/// Reads a JSON config file, falling back to the default config when the file
+/// is missing or cannot be parsed. Relative paths are read from the working
+/// directory, next to `ems_data.db`.
+pub fn load_json_config<T: DeserializeOwned + Default>(path: &str) -> T {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|err| {
+            eprintln!("Ignoring invalid config file {}: {}", path, err);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
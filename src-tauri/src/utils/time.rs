@@ -0,0 +1,19 @@
+use chrono::{Duration, Local, NaiveDate, TimeZone};
+
+/// Returns the range as Unix timestamps; dates are `YYYY-MM-DD` in local time and `to` is inclusive.
+/// A missing or unparsable bound leaves that side of the range open.
+pub fn local_date_range(from: Option<String>, to: Option<String>) -> (i64, i64) {
+    let parse = |date: Option<String>, days_after: i64| {
+        date.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+            .and_then(|d| (d + Duration::days(days_after)).and_hms_opt(0, 0, 0))
+            .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+            .map(|dt| dt.timestamp())
+    };
+    (parse(from, 0).unwrap_or(0), parse(to, 1).unwrap_or(i64::MAX))
+}
+
+/// Formats seconds as `HH:MM:SS`; hours are not capped at 24.
+pub fn format_duration(seconds: i64) -> String {
+    let duration = Duration::seconds(seconds);
+    format!("{:02}:{:02}:{:02}", duration.num_hours(), duration.num_minutes() % 60, duration.num_seconds() % 60)
+}
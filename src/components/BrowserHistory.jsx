@@ -11,15 +11,17 @@ const BrowserHistory = () => {
   const [selectedGmail, setSelectedGmail] = useState("");
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState(null);
+  const [failures, setFailures] = useState([]);
 
   const fetchBrowserHistory = async () => {
     setLoading(true);
     setError(null);
     try {
       const response = await invoke("get_browser_history");
-      const parsedData = JSON.parse(response);
+      const { history: parsedData = [], failures: failedProfiles = [] } = JSON.parse(response);
       console.log("Browser History:", parsedData);
       setHistory(parsedData);
+      setFailures(failedProfiles);
 
       // Extract unique browsers
       const uniqueBrowsers = [...new Set(parsedData.map(entry => entry.browser))];
@@ -91,6 +93,13 @@ const BrowserHistory = () => {
         <p className="text-center text-red-600 text-lg">{error}</p>
       ) : (
         <>
+          {failures.length > 0 && (
+            <p className="mb-4 text-yellow-700">
+              Could not read {failures.length} profile(s):{" "}
+              {failures.map(f => `${f.display_name} (${f.browser})`).join(", ")}
+            </p>
+          )}
+
           {/* Dropdown to Select Browser */}
           <div className="mb-4">
             <label className="block text-lg font-semibold mb-2">Select Browser:</label>